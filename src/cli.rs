@@ -0,0 +1,276 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use gbfr_build_calculator::solver::{GeneticSolver, Objective};
+
+/// finds combinations of sigils, a wrightstone and a weapon that reach the desired trait levels
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    after_help = "Exit status is 0 if builds were found, 1 if no build satisfies the query \
+    and 2 if the arguments or input files are invalid."
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// searches the inventory for builds that satisfy the query
+    Search(SearchArgs),
+    /// explains why no build satisfies the query and shows the closest build
+    Diagnose(InputArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct SearchArgs {
+    #[command(flatten)]
+    pub input: InputArgs,
+
+    #[arg(long, value_enum, default_value_t = SolverKind::BranchBound)]
+    pub solver: SolverKind,
+    #[command(flatten)]
+    pub genetic: GeneticArgs,
+
+    /// the maximum number of builds to print
+    #[arg(long, default_value_t = 10, value_parser = at_least_one)]
+    pub limit: usize,
+    /// print the best builds under this objective instead of the first builds found.
+    /// queries with optional traits default to `optional-traits`
+    #[arg(long, value_enum, value_name = "OBJECTIVE")]
+    pub best: Option<ObjectiveArg>,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+/// the inventory and query files
+#[derive(Debug, Args)]
+pub struct InputArgs {
+    /// the sigils to build from
+    #[arg(long, value_name = "FILE")]
+    pub sigils: PathBuf,
+    /// the wrightstones to choose from
+    #[arg(long, value_name = "FILE")]
+    pub wrightstones: Option<PathBuf>,
+    /// the weapons to choose from
+    #[arg(long, value_name = "FILE")]
+    pub weapons: Option<PathBuf>,
+    #[command(flatten)]
+    pub query: QueryArgs,
+    /// read misspelled trait and character names as the closest known name
+    #[arg(long)]
+    pub fuzzy: bool,
+}
+
+/// the query, read from a file, given on the command line, or both
+#[derive(Debug, Args)]
+#[command(next_help_heading = "Query")]
+pub struct QueryArgs {
+    /// the number of sigil slots and the desired traits
+    #[arg(long = "query", value_name = "FILE", required_unless_present = "slots")]
+    pub file: Option<PathBuf>,
+    /// a desired trait, replacing its level in the query file.
+    /// levels of a trait wanted several times add up
+    #[arg(long, value_name = "TRAIT=LEVEL")]
+    pub want: Vec<String>,
+    /// a trait no build may have
+    #[arg(long, value_name = "TRAIT")]
+    pub exclude: Vec<String>,
+    /// the highest level allowed for a trait, replacing its max level in the query file
+    #[arg(long, value_name = "TRAIT=LEVEL")]
+    pub max: Vec<String>,
+    /// the number of sigil slots, replacing the one in the query file
+    #[arg(long)]
+    pub slots: Option<u8>,
+    /// find minimal builds that may leave sigil slots empty instead of filling every slot
+    #[arg(long)]
+    pub allow_empty_slots: bool,
+    /// a sigil every build has to use, by its number in the sigils file counting every copy
+    #[arg(long, value_name = "N")]
+    pub lock_sigil: Vec<String>,
+    /// the wrightstone every build has to use, by its number in the wrightstones file
+    #[arg(long, value_name = "N", requires = "wrightstones")]
+    pub lock_wrightstone: Option<String>,
+}
+
+/// tuning of the genetic solver, ignored by the other solvers
+#[derive(Debug, Args)]
+#[command(next_help_heading = "Genetic solver")]
+pub struct GeneticArgs {
+    #[arg(long, default_value_t = 100, value_parser = at_least_one)]
+    pub population_size: usize,
+    #[arg(long, default_value_t = 200)]
+    pub generations: usize,
+    #[arg(long, default_value_t = 3, value_parser = at_least_one)]
+    pub tournament_size: usize,
+    #[arg(long, default_value_t = 0.6, value_parser = probability)]
+    pub crossover_rate: f64,
+    #[arg(long, default_value_t = 0.05, value_parser = probability)]
+    pub mutation_rate: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SolverKind {
+    /// tries every combination
+    Naive,
+    /// exact search that skips combinations which cannot reach the desired levels
+    BranchBound,
+    /// approximate search, fast on large inventories but may miss builds
+    Genetic,
+}
+
+/// the objectives of `Objective`, named for the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ObjectiveArg {
+    TotalSurplus,
+    FreeTraitSlots,
+    BonusTraits,
+    LeastWaste,
+    OptionalTraits,
+    /// the fewest sigils, then the most total surplus, with slots left empty
+    FreeSlots,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// aligned tables for the terminal
+    Text,
+    Markdown,
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+impl From<&GeneticArgs> for GeneticSolver {
+    fn from(args: &GeneticArgs) -> Self {
+        GeneticSolver {
+            population_size: args.population_size,
+            generations: args.generations,
+            tournament_size: args.tournament_size,
+            crossover_rate: args.crossover_rate,
+            mutation_rate: args.mutation_rate,
+        }
+    }
+}
+
+impl From<ObjectiveArg> for Objective {
+    fn from(objective: ObjectiveArg) -> Self {
+        match objective {
+            ObjectiveArg::TotalSurplus => Objective::TotalSurplus,
+            ObjectiveArg::FreeTraitSlots => Objective::FreeTraitSlots,
+            ObjectiveArg::BonusTraits => Objective::BonusTraits,
+            ObjectiveArg::LeastWaste => Objective::LeastWaste,
+            ObjectiveArg::OptionalTraits => Objective::OptionalTraits,
+            ObjectiveArg::FreeSlots => Objective::FreeSlots,
+        }
+    }
+}
+
+fn at_least_one(arg: &str) -> Result<usize, String> {
+    match arg.parse() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(err) => Err(format!("{err}")),
+    }
+}
+
+fn probability(arg: &str) -> Result<f64, String> {
+    match arg.parse() {
+        Ok(p) if (0.0..=1.0).contains(&p) => Ok(p),
+        Ok(_) => Err("must be between 0 and 1".to_string()),
+        Err(err) => Err(format!("{err}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn search_arguments() {
+        let cli = Cli::try_parse_from([
+            "gbfr-build-calculator",
+            "search",
+            "--sigils",
+            "sigils.csv",
+            "--query",
+            "query.csv",
+            "--solver",
+            "genetic",
+            "--generations",
+            "50",
+            "--best",
+            "free-slots",
+        ])
+        .unwrap();
+
+        let Command::Search(args) = cli.command else {
+            panic!("expected the search command");
+        };
+        assert_eq!(args.solver, SolverKind::Genetic);
+        assert_eq!(args.genetic.generations, 50);
+        assert_eq!(args.best, Some(ObjectiveArg::FreeSlots));
+        assert_eq!(args.input.wrightstones, None);
+        assert_eq!(args.limit, 10);
+    }
+
+    #[test]
+    fn inline_query_needs_slots_without_file() {
+        let args = ["gbfr-build-calculator", "search", "--sigils", "sigils.csv"];
+        let wants = ["--want", "DMG Cap=55", "--want", "Gamma=30"];
+
+        let err = Cli::try_parse_from(args.iter().chain(&wants)).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+
+        let cli = Cli::try_parse_from(args.iter().chain(&wants).chain(&["--slots", "12"])).unwrap();
+        let Command::Search(args) = cli.command else {
+            panic!("expected the search command");
+        };
+        assert_eq!(args.input.query.want, ["DMG Cap=55", "Gamma=30"]);
+        assert!(args.input.query.exclude.is_empty());
+        assert!(args.input.query.lock_sigil.is_empty());
+        assert_eq!(args.input.query.file, None);
+    }
+
+    #[test]
+    fn rejects_invalid_rates() {
+        let err = Cli::try_parse_from([
+            "gbfr-build-calculator",
+            "search",
+            "--sigils",
+            "sigils.csv",
+            "--query",
+            "query.csv",
+            "--mutation-rate",
+            "1.5",
+        ])
+        .unwrap_err();
+
+        assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+    }
+
+    #[test]
+    fn rejects_zero_limit() {
+        let err = Cli::try_parse_from([
+            "gbfr-build-calculator",
+            "search",
+            "--sigils",
+            "sigils.csv",
+            "--query",
+            "query.csv",
+            "--limit",
+            "0",
+        ])
+        .unwrap_err();
+
+        assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+    }
+}
@@ -0,0 +1,126 @@
+use std::fmt::Display;
+
+use nom::error::{VerboseError, VerboseErrorKind};
+
+#[cfg(feature = "serde")]
+use super::FormatError;
+
+/// an error in an input file, pointing at the text that could not be parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// the name of the file, if known
+    pub file: Option<String>,
+    /// the line of the error, starting at 1
+    pub line: usize,
+    /// the column of the error in characters, starting at 1
+    pub column: usize,
+    /// what was expected at the error position
+    pub expected: String,
+    /// the offending text
+    pub text: String,
+    /// the closest valid name if the text looks like a misspelled name
+    pub suggestion: Option<String>,
+}
+
+impl ParseError {
+    pub(super) fn new(
+        line: usize,
+        column: usize,
+        expected: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        ParseError {
+            file: None,
+            line,
+            column,
+            expected: expected.into(),
+            text: text.into(),
+            suggestion: None,
+        }
+    }
+
+    pub(super) fn with_suggestion(mut self, suggestion: Option<String>) -> Self {
+        self.suggestion = suggestion;
+        self
+    }
+
+    /// attaches the name of the file the error occurred in
+    pub fn in_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{file}:")?;
+        }
+        write!(
+            f,
+            "{}:{}: expected {}",
+            self.line, self.column, self.expected
+        )?;
+
+        if self.text.is_empty() {
+            write!(f, ", found nothing")?;
+        } else {
+            write!(f, ", found `{}`", self.text)?;
+        }
+
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "\n  did you mean `{suggestion}`?")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// an error reading an input file from disk
+#[derive(Debug)]
+pub enum LoadError {
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+    /// an error in a CSV file, which knows the name of the file
+    Parse(ParseError),
+    #[cfg(feature = "serde")]
+    Format { path: String, source: FormatError },
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Read { path, source } => write!(f, "could not read {path}: {source}"),
+            LoadError::Parse(err) => write!(f, "{err}"),
+            #[cfg(feature = "serde")]
+            LoadError::Format { path, source } => write!(f, "{path}: {source}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Read { source, .. } => Some(source),
+            LoadError::Parse(err) => Some(err),
+            #[cfg(feature = "serde")]
+            LoadError::Format { source, .. } => Some(source),
+        }
+    }
+}
+
+/// the position and label of the innermost `context` the error passed through,
+/// which describes what failed to parse
+pub(super) fn innermost_context(err: VerboseError<&str>) -> (Option<&str>, &'static str) {
+    err.errors
+        .iter()
+        .find_map(|(input, kind)| match kind {
+            VerboseErrorKind::Context(label) => Some((Some(*input), *label)),
+            _ => None,
+        })
+        .unwrap_or_else(|| (err.errors.first().map(|(input, _)| *input), "valid input"))
+}
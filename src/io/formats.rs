@@ -0,0 +1,156 @@
+use std::fmt::Display;
+
+use serde::de::DeserializeOwned;
+
+/// a format for input files other than CSV, chosen by the file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Toml,
+}
+
+/// an error in a JSON or TOML input file
+#[derive(Debug)]
+pub enum FormatError {
+    Json(serde_json::Error),
+    Toml(toml::de::Error),
+    /// a TOML file without the array of tables holding the items
+    MissingTable(String),
+}
+
+impl Format {
+    /// the format named by the extension of `path`, or `None` for CSV files
+    pub fn of_path(path: &str) -> Option<Format> {
+        let extension = path.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+        match extension.as_deref() {
+            Some("json") => Some(Format::Json),
+            Some("toml") => Some(Format::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// reads a list of items, stored as an array in JSON files
+/// and as an array of tables named `key` in TOML files
+pub(super) fn items<T: DeserializeOwned>(
+    format: Format,
+    key: &str,
+    input: &str,
+) -> Result<Vec<T>, FormatError> {
+    match format {
+        Format::Json => serde_json::from_str(input).map_err(FormatError::Json),
+        Format::Toml => {
+            let mut table: toml::Table = toml::from_str(input).map_err(FormatError::Toml)?;
+            match table.remove(key) {
+                Some(items) => items.try_into().map_err(FormatError::Toml),
+                None => Err(FormatError::MissingTable(key.to_string())),
+            }
+        }
+    }
+}
+
+/// reads a single value stored as an object in JSON files and as a table in TOML files
+pub(super) fn value<T: DeserializeOwned>(format: Format, input: &str) -> Result<T, FormatError> {
+    match format {
+        Format::Json => serde_json::from_str(input).map_err(FormatError::Json),
+        Format::Toml => toml::from_str(input).map_err(FormatError::Toml),
+    }
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormatError::Json(err) => write!(f, "{err}"),
+            FormatError::Toml(err) => write!(f, "{err}"),
+            FormatError::MissingTable(key) => {
+                write!(f, "expected an array of tables named `[[{key}]]`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Character, SearchQuery, Sigil, Trait, TraitKind as TK, Wrightstone};
+
+    use super::*;
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(Format::of_path("inventory/sigils.JSON"), Some(Format::Json));
+        assert_eq!(Format::of_path("query.toml"), Some(Format::Toml));
+        assert_eq!(Format::of_path("sigils.csv"), None);
+        assert_eq!(Format::of_path("sigils"), None);
+    }
+
+    #[test]
+    fn json_sigils() {
+        let input = r#"[
+            {"trait1": {"kind": "Aegis", "level": 15}},
+            {"trait1": {"kind": "Alpha", "level": 12}, "trait2": {"kind": "dmg cap", "level": 12}}
+        ]"#;
+
+        let sigils: Vec<Sigil> = items(Format::Json, "sigils", input).unwrap();
+
+        assert_eq!(sigils[0], Sigil::new_single(Trait::new(TK::Aegis, 15)));
+        assert_eq!(sigils[1].trait2, Some(Trait::new(TK::DMGCap, 12)));
+    }
+
+    #[test]
+    fn toml_wrightstones() {
+        let input = r#"
+            [[wrightstones]]
+            trait1 = { kind = "Critical Hit Rate", level = 10 }
+            trait2 = { kind = "HP", level = 6 }
+        "#;
+
+        let stones: Vec<Wrightstone> = items(Format::Toml, "wrightstones", input).unwrap();
+
+        assert_eq!(stones.len(), 1);
+        assert_eq!(stones[0].trait2, Some(Trait::new(TK::HP, 6)));
+        assert_eq!(stones[0].trait3, None);
+    }
+
+    #[test]
+    fn toml_without_item_table_is_an_error() {
+        let input = r#"
+            [[sigil]]
+            trait1 = { kind = "Aegis", level = 15 }
+        "#;
+
+        let err = items::<Sigil>(Format::Toml, "sigils", input).unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "expected an array of tables named `[[sigils]]`"
+        );
+    }
+
+    #[test]
+    fn toml_query() {
+        let input = r#"
+            sigil_slots = 12
+            character = "Zeta"
+
+            [desired_traits]
+            "DMG Cap" = 45
+            Gamma = 30
+        "#;
+
+        let query: SearchQuery = value(Format::Toml, input).unwrap();
+
+        assert_eq!(query.character, Some(Character::Zeta));
+        assert_eq!(query.desired_traits.level(TK::DMGCap), 45);
+    }
+
+    #[test]
+    fn unknown_trait_is_an_error() {
+        let input = r#"[{"trait1": {"kind": "Gama", "level": 15}}]"#;
+
+        let err = items::<Sigil>(Format::Json, "sigils", input).unwrap_err();
+
+        assert!(err.to_string().contains("invalid trait name `Gama`"));
+    }
+}
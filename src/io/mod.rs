@@ -0,0 +1,937 @@
+//! reading and writing of inventory and query files
+
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::model::{
+    Character, OptionalTrait, SearchQuery, Sigil, Trait, TraitKind, TraitSet, Weapon, Wrightstone,
+};
+
+use table::{content_lines, rows, split_fields, Column, Field, Layout, Row};
+
+pub use error::{LoadError, ParseError};
+#[cfg(feature = "serde")]
+pub use formats::{Format, FormatError};
+pub use writer::ToCsv;
+
+mod error;
+#[cfg(feature = "serde")]
+mod formats;
+mod table;
+mod writer;
+
+const SIGIL: Layout = Layout {
+    columns: &[
+        Column::Trait(1),
+        Column::Level(1),
+        Column::Trait(2),
+        Column::Level(2),
+        Column::Quantity,
+    ],
+    required: &[Column::Trait(1), Column::Level(1)],
+};
+
+const WRIGHTSTONE: Layout = Layout {
+    columns: &[
+        Column::Trait(1),
+        Column::Level(1),
+        Column::Trait(2),
+        Column::Level(2),
+        Column::Trait(3),
+        Column::Level(3),
+        Column::Quantity,
+    ],
+    required: &[Column::Trait(1), Column::Level(1)],
+};
+
+const WEAPON: Layout = Layout {
+    columns: &[
+        Column::Name,
+        Column::Trait(1),
+        Column::Level(1),
+        Column::Trait(2),
+        Column::Level(2),
+        Column::Trait(3),
+        Column::Level(3),
+    ],
+    required: &[Column::Name, Column::Trait(1), Column::Level(1)],
+};
+
+const QUERY_TRAIT: Layout = Layout {
+    columns: &[
+        Column::Trait(1),
+        Column::Level(1),
+        Column::Weight,
+        Column::Max,
+    ],
+    required: &[Column::Trait(1)],
+};
+
+/// parses input files, optionally resolving misspelled names.
+///
+/// every file is a list of comma separated lines. blank lines and lines starting with `#`
+/// are skipped, and the first line of a list may be a header row naming its columns
+#[derive(Debug, Default)]
+pub struct Parser {
+    /// replace trait and character names that are not recognized with the closest known name
+    /// instead of failing, if one is close enough
+    pub fuzzy: bool,
+    /// every name replaced in fuzzy mode so far
+    pub corrections: Vec<Correction>,
+}
+
+/// a misspelled name that was replaced in fuzzy mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct Correction {
+    pub line: usize,
+    pub column: usize,
+    /// the name as written in the input
+    pub text: String,
+    /// the name it was read as
+    pub corrected: String,
+}
+
+impl Parser {
+    /// reads the file at `path`. with the `serde` feature, files ending in `.json` or `.toml`
+    /// are read in those formats, all other files as CSV
+    pub fn read_file<T: InputFile>(&mut self, path: impl AsRef<Path>) -> Result<T, LoadError> {
+        let path = path.as_ref();
+        let input = std::fs::read_to_string(path).map_err(|source| LoadError::Read {
+            path: path.display().to_string(),
+            source,
+        })?;
+
+        #[cfg(feature = "serde")]
+        if let Some(format) = Format::of_path(&path.to_string_lossy()) {
+            return T::deserialize(format, &input).map_err(|source| LoadError::Format {
+                path: path.display().to_string(),
+                source,
+            });
+        }
+
+        T::parse_csv(self, &input)
+            .map_err(|err| LoadError::Parse(err.in_file(path.display().to_string())))
+    }
+
+    /// one sigil per line, with an optional column for the number of identical copies
+    pub fn sigils(&mut self, input: &str) -> Result<Vec<Sigil>, ParseError> {
+        let mut sigils = Vec::new();
+
+        for row in rows(content_lines(input), &SIGIL)? {
+            let sigil = Sigil {
+                trait1: self.required_trait(&row, 1)?,
+                trait2: self.optional_trait(&row, 2)?,
+            };
+            let count = quantity(&row)?;
+            sigils.extend(std::iter::repeat_n(sigil, count));
+        }
+
+        Ok(sigils)
+    }
+
+    /// one wrightstone per line, with an optional column for the number of identical copies
+    pub fn wrightstones(&mut self, input: &str) -> Result<Vec<Wrightstone>, ParseError> {
+        let mut wrightstones = Vec::new();
+
+        for row in rows(content_lines(input), &WRIGHTSTONE)? {
+            let wrightstone = Wrightstone {
+                trait1: self.required_trait(&row, 1)?,
+                trait2: self.optional_trait(&row, 2)?,
+                trait3: self.optional_trait(&row, 3)?,
+            };
+            let count = quantity(&row)?;
+            wrightstones.extend(std::iter::repeat_n(wrightstone, count));
+        }
+
+        Ok(wrightstones)
+    }
+
+    pub fn weapons(&mut self, input: &str) -> Result<Vec<Weapon>, ParseError> {
+        rows(content_lines(input), &WEAPON)?
+            .iter()
+            .map(|row| {
+                let name = row
+                    .get(Column::Name)
+                    .ok_or_else(|| row.missing(Column::Name, "weapon name"))?;
+
+                Ok(Weapon {
+                    name: name.text.to_string(),
+                    trait1: self.required_trait(row, 1)?,
+                    trait2: self.optional_trait(row, 2)?,
+                    trait3: self.optional_trait(row, 3)?,
+                })
+            })
+            .collect()
+    }
+
+    /// the first line of a query is either the number of sigil slots, a character name,
+    /// or a character name followed by the number of sigil slots.
+    /// every following line is a desired trait and its level,
+    /// or an optional trait if the line also has a weight.
+    /// a line can also give the trait's max level, and then needs no level
+    pub fn query(&mut self, input: &str) -> Result<SearchQuery, ParseError> {
+        let mut lines = content_lines(input);
+        let (line_number, line) = lines
+            .next()
+            .ok_or_else(|| ParseError::new(1, 1, "number of sigil slots or character name", ""))?;
+
+        let (character, sigil_slots) = match split_fields(line_number, line)?.as_slice() {
+            [field] if field.text.starts_with(|c: char| c.is_ascii_digit()) => {
+                (None, number(line_number, field, "number of sigil slots")?)
+            }
+            [field] => {
+                let character = self.character(line_number, field)?;
+                (Some(character), character.sigil_slots())
+            }
+            [character, slots] => (
+                Some(self.character(line_number, character)?),
+                number(line_number, slots, "number of sigil slots")?,
+            ),
+            [_, _, extra, ..] => {
+                return Err(ParseError::new(
+                    line_number,
+                    extra.column,
+                    "end of line",
+                    extra.text.as_ref(),
+                ))
+            }
+            [] => unreachable!("a line has at least one field"),
+        };
+
+        let mut desired_traits = TraitSet::new();
+        let mut optional_traits = Vec::new();
+        let mut max_levels = TraitSet::new();
+        for row in rows(lines, &QUERY_TRAIT)? {
+            let name = row
+                .get(Column::Trait(1))
+                .ok_or_else(|| row.missing(Column::Trait(1), "trait name"))?;
+            let kind = self.trait_kind(row.line, name)?;
+
+            let max = row.get(Column::Max);
+            if let Some(max) = max {
+                max_levels.insert(kind, number(row.line, max, "max level")?);
+            }
+
+            match (row.get(Column::Level(1)), row.get(Column::Weight)) {
+                (None, None) if max.is_some() => {}
+                (None, _) => return Err(row.missing(Column::Level(1), "trait level")),
+                (Some(level), None) => desired_traits.add(Trait {
+                    kind,
+                    level: number(row.line, level, "trait level")?,
+                }),
+                (Some(level), Some(weight)) => optional_traits.push(OptionalTrait {
+                    kind,
+                    level: number(row.line, level, "trait level")?,
+                    weight: number(row.line, weight, "weight")?,
+                }),
+            }
+        }
+
+        let query = SearchQuery {
+            desired_traits,
+            sigil_slots,
+            character,
+            optional_traits,
+            max_levels,
+            ..Default::default()
+        };
+
+        Ok(query)
+    }
+
+    /// a trait and level written as `name=level`, the way traits are given on the command line.
+    /// errors point at line 1 of `text`
+    pub fn wanted_trait(&mut self, text: &str) -> Result<Trait, ParseError> {
+        let Some((name, level)) = text.split_once('=') else {
+            return Err(ParseError::new(1, text.chars().count() + 1, "`=`", ""));
+        };
+        let name = inline_field(text, 0, name);
+        if name.text.is_empty() {
+            return Err(ParseError::new(1, name.column, "trait name", ""));
+        }
+        let level = inline_field(text, text.len() - level.len(), level);
+        if level.text.is_empty() {
+            return Err(ParseError::new(1, level.column, "trait level", ""));
+        }
+
+        Ok(Trait {
+            kind: self.trait_kind(1, &name)?,
+            level: number(1, &level, "trait level")?,
+        })
+    }
+
+    /// a trait name given on the command line. errors point at line 1 of `text`
+    pub fn trait_name(&mut self, text: &str) -> Result<TraitKind, ParseError> {
+        let name = inline_field(text, 0, text);
+        if name.text.is_empty() {
+            return Err(ParseError::new(1, name.column, "trait name", ""));
+        }
+        self.trait_kind(1, &name)
+    }
+
+    /// the index of an item given on the command line by its number in a list of `count`
+    /// items, starting at 1. errors point at line 1 of `text`
+    pub fn item_number(&self, text: &str, count: usize) -> Result<usize, ParseError> {
+        let field = inline_field(text, 0, text);
+        let expected = format!("an item number from 1 to {count}");
+        match number::<usize>(1, &field, &expected)? {
+            n @ 1.. if n <= count => Ok(n - 1),
+            _ => Err(ParseError::new(1, field.column, expected, field.text)),
+        }
+    }
+
+    /// the indexes of items given on the command line by their numbers in a list of `count`
+    /// items, each number at most once. errors point at line 1 of the offending text
+    pub fn item_numbers(&self, texts: &[String], count: usize) -> Result<Vec<usize>, ParseError> {
+        let mut indexes = Vec::with_capacity(texts.len());
+        for text in texts {
+            let i = self.item_number(text, count)?;
+            if indexes.contains(&i) {
+                let field = inline_field(text, 0, text);
+                return Err(ParseError::new(
+                    1,
+                    field.column,
+                    "an item number not given before",
+                    field.text,
+                ));
+            }
+            indexes.push(i);
+        }
+        Ok(indexes)
+    }
+
+    /// the `n`-th trait of the row, which has to be filled in
+    fn required_trait(&mut self, row: &Row, n: u8) -> Result<Trait, ParseError> {
+        let kind = row
+            .get(Column::Trait(n))
+            .ok_or_else(|| row.missing(Column::Trait(n), "trait name"))?;
+        let kind = self.trait_kind(row.line, kind)?;
+
+        let level = row
+            .get(Column::Level(n))
+            .ok_or_else(|| row.missing(Column::Level(n), "trait level"))?;
+        let level = number(row.line, level, "trait level")?;
+
+        Ok(Trait { kind, level })
+    }
+
+    /// the `n`-th trait of the row, or `None` if both its name and level are empty
+    fn optional_trait(&mut self, row: &Row, n: u8) -> Result<Option<Trait>, ParseError> {
+        if row.get(Column::Trait(n)).is_none() && row.get(Column::Level(n)).is_none() {
+            return Ok(None);
+        }
+        self.required_trait(row, n).map(Some)
+    }
+
+    fn trait_kind(&mut self, line: usize, field: &Field) -> Result<TraitKind, ParseError> {
+        match TraitKind::from_name(&field.text) {
+            Some(kind) => Ok(kind),
+            None => {
+                let closest = TraitKind::closest(&field.text).map(|kind| (kind, kind.name()));
+                self.correct(line, field, "trait name", closest)
+            }
+        }
+    }
+
+    fn character(&mut self, line: usize, field: &Field) -> Result<Character, ParseError> {
+        match field.text.parse::<Character>() {
+            Ok(character) => Ok(character),
+            Err(_) => {
+                let closest = Character::closest(&field.text).map(|c| (c, c.name()));
+                self.correct(line, field, "character name", closest)
+            }
+        }
+    }
+
+    /// reads a name that was not recognized as the closest known name in fuzzy mode,
+    /// or fails with the closest name as suggestion
+    fn correct<T>(
+        &mut self,
+        line: usize,
+        field: &Field,
+        expected: &str,
+        closest: Option<(T, &str)>,
+    ) -> Result<T, ParseError> {
+        match closest {
+            Some((value, name)) if self.fuzzy => {
+                self.corrections.push(Correction {
+                    line,
+                    column: field.column,
+                    text: field.text.to_string(),
+                    corrected: name.to_string(),
+                });
+                Ok(value)
+            }
+            _ => Err(
+                ParseError::new(line, field.column, expected, field.text.as_ref())
+                    .with_suggestion(closest.map(|(_, name)| name.to_string())),
+            ),
+        }
+    }
+}
+
+/// the contents of an input file
+pub trait InputFile: Sized {
+    fn parse_csv(parser: &mut Parser, input: &str) -> Result<Self, ParseError>;
+
+    #[cfg(feature = "serde")]
+    fn deserialize(format: Format, input: &str) -> Result<Self, FormatError>;
+}
+
+impl InputFile for Vec<Sigil> {
+    fn parse_csv(parser: &mut Parser, input: &str) -> Result<Self, ParseError> {
+        parser.sigils(input)
+    }
+
+    #[cfg(feature = "serde")]
+    fn deserialize(format: Format, input: &str) -> Result<Self, FormatError> {
+        formats::items(format, "sigils", input)
+    }
+}
+
+impl InputFile for Vec<Wrightstone> {
+    fn parse_csv(parser: &mut Parser, input: &str) -> Result<Self, ParseError> {
+        parser.wrightstones(input)
+    }
+
+    #[cfg(feature = "serde")]
+    fn deserialize(format: Format, input: &str) -> Result<Self, FormatError> {
+        formats::items(format, "wrightstones", input)
+    }
+}
+
+impl InputFile for Vec<Weapon> {
+    fn parse_csv(parser: &mut Parser, input: &str) -> Result<Self, ParseError> {
+        parser.weapons(input)
+    }
+
+    #[cfg(feature = "serde")]
+    fn deserialize(format: Format, input: &str) -> Result<Self, FormatError> {
+        formats::items(format, "weapons", input)
+    }
+}
+
+impl InputFile for SearchQuery {
+    fn parse_csv(parser: &mut Parser, input: &str) -> Result<Self, ParseError> {
+        parser.query(input)
+    }
+
+    #[cfg(feature = "serde")]
+    fn deserialize(format: Format, input: &str) -> Result<Self, FormatError> {
+        formats::value(format, input)
+    }
+}
+
+impl Display for Correction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}: read `{}` as `{}`",
+            self.line, self.column, self.text, self.corrected
+        )
+    }
+}
+
+/// the number of identical copies a row stands for, 1 if not given
+fn quantity(row: &Row) -> Result<usize, ParseError> {
+    row.get(Column::Quantity)
+        .map_or(Ok(1), |field| number::<u16>(row.line, field, "quantity"))
+        .map(usize::from)
+}
+
+/// the field for `part` of a command line argument, which starts at byte `start` of `text`
+fn inline_field<'a>(text: &str, start: usize, part: &'a str) -> Field<'a> {
+    let leading_spaces = part.len() - part.trim_start().len();
+    Field {
+        text: Cow::Borrowed(part.trim()),
+        column: text[..start + leading_spaces].chars().count() + 1,
+    }
+}
+
+fn number<T: FromStr>(line: usize, field: &Field, expected: &str) -> Result<T, ParseError> {
+    field
+        .text
+        .parse()
+        .map_err(|_| ParseError::new(line, field.column, expected, field.text.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use TraitKind as TK;
+
+    use super::*;
+
+    // macro_rules! _trait {
+    //     ($kind:ident, $level:expr) => {
+    //         Trait {
+    //             kind: $kind,
+    //             level:
+    //         }
+    //     };
+    // }
+
+    #[test]
+    fn one_sigil_single_trait() {
+        let input = "Aegis,15,,";
+        let expected = vec![Sigil {
+            trait1: Trait {
+                kind: TraitKind::Aegis,
+                level: 15,
+            },
+            trait2: None,
+        }];
+
+        let parsed = Parser::default().sigils(input).unwrap();
+
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn one_sigil_double_trait() {
+        let input = "Alpha,12,DMG Cap,12";
+        let expected = vec![Sigil {
+            trait1: Trait {
+                kind: TraitKind::Alpha,
+                level: 12,
+            },
+            trait2: Some(Trait {
+                kind: TraitKind::DMGCap,
+                level: 12,
+            }),
+        }];
+
+        let parsed = Parser::default().sigils(input).unwrap();
+
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn multi_sigil() {
+        let input = "Aegis,15,,\n\
+        Alpha,12,DMG Cap,12";
+
+        let expected = vec![
+            Sigil {
+                trait1: Trait {
+                    kind: TraitKind::Aegis,
+                    level: 15,
+                },
+                trait2: None,
+            },
+            Sigil {
+                trait1: Trait {
+                    kind: TraitKind::Alpha,
+                    level: 12,
+                },
+                trait2: Some(Trait {
+                    kind: TraitKind::DMGCap,
+                    level: 12,
+                }),
+            },
+        ];
+
+        let parsed = Parser::default().sigils(input).unwrap();
+
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn sigil_quantity() {
+        let input = "Aegis,15,,,3\n\
+        Alpha,12,DMG Cap,12";
+
+        let parsed = Parser::default().sigils(input).unwrap();
+
+        assert_eq!(parsed.len(), 4);
+        assert!(parsed[..3]
+            .iter()
+            .all(|sigil| sigil.trait1 == Trait::new(TK::Aegis, 15)));
+        assert_eq!(parsed[3].trait1, Trait::new(TK::Alpha, 12));
+    }
+
+    #[test]
+    fn error_points_at_misspelled_trait() {
+        let input = "Aegis,15,,\n\
+        Alpha,12,Critcal Hit Rate,12";
+
+        let err = Parser::default()
+            .sigils(input)
+            .unwrap_err()
+            .in_file("sigils.csv");
+
+        assert_eq!(err.line, 2);
+        assert_eq!(err.column, 10);
+        assert_eq!(err.text, "Critcal Hit Rate");
+        assert_eq!(err.suggestion.as_deref(), Some("Critical Hit Rate"));
+        assert_eq!(
+            err.to_string(),
+            "sigils.csv:2:10: expected trait name, found `Critcal Hit Rate`\n  \
+            did you mean `Critical Hit Rate`?"
+        );
+    }
+
+    #[test]
+    fn error_on_invalid_level() {
+        let err = Parser::default().sigils("Aegis,300,,").unwrap_err();
+
+        assert_eq!((err.line, err.column), (1, 7));
+        assert_eq!(err.expected, "trait level");
+        assert_eq!(err.text, "300");
+        assert_eq!(err.suggestion, None);
+    }
+
+    #[test]
+    fn fuzzy_mode_corrects_names() {
+        let input = "Alpha,12,Critcal Hit Rte,12\n\
+        Gama,15,Dmg Cap,15";
+        let mut parser = Parser {
+            fuzzy: true,
+            ..Default::default()
+        };
+
+        let parsed = parser.sigils(input).unwrap();
+
+        assert_eq!(parsed[0].trait2, Some(Trait::new(TK::CriticalHitRate, 12)));
+        assert_eq!(parsed[1].trait1, Trait::new(TK::Gamma, 15));
+        assert_eq!(
+            parser.corrections,
+            [
+                Correction {
+                    line: 1,
+                    column: 10,
+                    text: "Critcal Hit Rte".to_string(),
+                    corrected: "Critical Hit Rate".to_string(),
+                },
+                Correction {
+                    line: 2,
+                    column: 1,
+                    text: "Gama".to_string(),
+                    corrected: "Gamma".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn strict_mode_rejects_misspelled_names() {
+        let err = Parser::default().sigils("Gama,15,,").unwrap_err();
+
+        assert_eq!(err.suggestion.as_deref(), Some("Gamma"));
+    }
+
+    #[test]
+    fn fuzzy_corrections_in_query_use_file_lines() {
+        let mut parser = Parser {
+            fuzzy: true,
+            ..Default::default()
+        };
+
+        let parsed = parser.query("Zeat\nDMG Cap,15\nGama,10").unwrap();
+
+        assert_eq!(parsed.character, Some(Character::Zeta));
+        assert_eq!(parsed.desired_traits.level(TK::Gamma), 10);
+        let lines: Vec<usize> = parser.corrections.iter().map(|c| c.line).collect();
+        assert_eq!(lines, [1, 3]);
+    }
+
+    #[test]
+    fn skips_blank_lines_and_comments() {
+        let input = "# my sigils\r\n\
+        Aegis,15,,\r\n\
+        \r\n\
+        \x20 # lv 12 ones\r\n\
+        Alpha,12,DMG Cap,12\r\n";
+
+        let parsed = Parser::default().sigils(input).unwrap();
+
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[1].trait2, Some(Trait::new(TK::DMGCap, 12)));
+    }
+
+    #[test]
+    fn header_names_columns_in_any_order() {
+        let input = "Level 1,Trait 1,Quantity,Trait 2,Level 2\n\
+        15,Aegis,2,,\n\
+        12,Alpha,,DMG Cap,12";
+
+        let parsed = Parser::default().sigils(input).unwrap();
+
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed[0], Sigil::new_single(Trait::new(TK::Aegis, 15)));
+        assert_eq!(parsed[2].trait1, Trait::new(TK::Alpha, 12));
+    }
+
+    #[test]
+    fn header_must_name_required_columns() {
+        let err = Parser::default()
+            .sigils("Trait 1,Trait 2\nAegis,")
+            .unwrap_err();
+
+        assert_eq!(err.line, 1);
+        assert_eq!(err.expected, "a `level 1` column");
+    }
+
+    #[test]
+    fn leftover_columns_are_an_error() {
+        let err = Parser::default().sigils("Aegis,15,,,1,extra").unwrap_err();
+
+        assert_eq!((err.line, err.column), (1, 14));
+        assert_eq!(err.expected, "end of line");
+        assert_eq!(err.text, "extra");
+    }
+
+    #[test]
+    fn trait_without_level_is_an_error() {
+        let err = Parser::default().sigils("Aegis,15,Drain,").unwrap_err();
+
+        assert_eq!((err.line, err.column), (1, 16));
+        assert_eq!(err.expected, "trait level");
+    }
+
+    #[test]
+    fn quoted_weapon_name() {
+        let input = r#""Sword, ""Great""",ATK,15,,,,"#;
+
+        let parsed = Parser::default().weapons(input).unwrap();
+
+        assert_eq!(parsed[0].name, r#"Sword, "Great""#);
+    }
+
+    #[test]
+    fn query_with_comments_and_header() {
+        let input = "# build for zeta\n\
+        Zeta\n\
+        Trait,Level\n\
+        DMG Cap,15\n";
+
+        let parsed = Parser::default().query(input).unwrap();
+
+        assert_eq!(parsed.character, Some(Character::Zeta));
+        assert_eq!(parsed.desired_traits.level(TK::DMGCap), 15);
+    }
+
+    #[test]
+    fn multi_wrightstone() {
+        let input = "Critical Hit Rate,10,HP,6,Uplift,3\n\
+        Stun Power,2,,,,";
+
+        let expected = vec![
+            Wrightstone {
+                trait1: Trait::new(TK::CriticalHitRate, 10),
+                trait2: Some(Trait::new(TK::HP, 6)),
+                trait3: Some(Trait::new(TK::Uplift, 3)),
+            },
+            Wrightstone {
+                trait1: Trait::new(TK::StunPower, 2),
+                trait2: None,
+                trait3: None,
+            },
+        ];
+
+        let parsed = Parser::default().wrightstones(input).unwrap();
+
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn multi_weapon() {
+        let input = "Eternal Sword,DMG Cap,10,Supplementary DMG,10,,\n\
+        Ascension Blade,ATK,15,,,,";
+
+        let expected = vec![
+            Weapon {
+                name: "Eternal Sword".to_string(),
+                trait1: Trait::new(TK::DMGCap, 10),
+                trait2: Some(Trait::new(TK::SupplementaryDamage, 10)),
+                trait3: None,
+            },
+            Weapon {
+                name: "Ascension Blade".to_string(),
+                trait1: Trait::new(TK::ATK, 15),
+                trait2: None,
+                trait3: None,
+            },
+        ];
+
+        let parsed = Parser::default().weapons(input).unwrap();
+
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn query_single_trait() {
+        let input = "5\n\
+        DMG Cap,15";
+
+        let mut desired_traits = TraitSet::new();
+        desired_traits.add(Trait::new(TK::DMGCap, 15));
+
+        let expected = SearchQuery {
+            desired_traits,
+            sigil_slots: 5,
+            ..Default::default()
+        };
+
+        let parsed = Parser::default().query(input).unwrap();
+
+        assert_eq!(parsed, expected);
+    }
+
+    #[test]
+    fn query_optional_traits() {
+        let input = "12\n\
+        Trait,Level,Weight\n\
+        DMG Cap,55\n\
+        Quick Cooldown,15,3\n\
+        Uplift,10,1";
+
+        let parsed = Parser::default().query(input).unwrap();
+
+        assert_eq!(parsed.desired_traits.level(TK::DMGCap), 55);
+        assert!(!parsed.desired_traits.contains(TK::QuickCooldown));
+        assert_eq!(
+            parsed.optional_traits,
+            [
+                OptionalTrait {
+                    kind: TK::QuickCooldown,
+                    level: 15,
+                    weight: 3
+                },
+                OptionalTrait {
+                    kind: TK::Uplift,
+                    level: 10,
+                    weight: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn query_max_levels() {
+        let input = "12\n\
+        Trait,Level,Max\n\
+        DMG Cap,45,65\n\
+        Glass Cannon,,0";
+
+        let parsed = Parser::default().query(input).unwrap();
+
+        assert_eq!(parsed.desired_traits.level(TK::DMGCap), 45);
+        assert!(!parsed.desired_traits.contains(TK::GlassCannon));
+        assert_eq!(parsed.max_level(TK::DMGCap), Some(65));
+        assert_eq!(parsed.max_level(TK::GlassCannon), Some(0));
+
+        let err = Parser::default().query("12\nGamma,,1").unwrap_err();
+        assert_eq!((err.line, err.expected.as_str()), (2, "trait level"));
+    }
+
+    #[test]
+    fn query_character_default_slots() {
+        let input = "Zeta\n\
+        DMG Cap,15";
+
+        let parsed = Parser::default().query(input).unwrap();
+
+        assert_eq!(parsed.character, Some(Character::Zeta));
+        assert_eq!(parsed.sigil_slots, Character::Zeta.sigil_slots());
+    }
+
+    #[test]
+    fn query_character_with_slots() {
+        let input = "Gran,10\n\
+        DMG Cap,15";
+
+        let parsed = Parser::default().query(input).unwrap();
+
+        assert_eq!(parsed.character, Some(Character::Captain));
+        assert_eq!(parsed.sigil_slots, 10);
+    }
+
+    #[test]
+    fn query_error_reports_line_in_file() {
+        let input = "Zeta\n\
+        DMG Cap,15\n\
+        Gama,10";
+
+        let err = Parser::default().query(input).unwrap_err();
+
+        assert_eq!((err.line, err.column), (3, 1));
+        assert_eq!(err.suggestion.as_deref(), Some("Gamma"));
+    }
+
+    #[test]
+    fn query_error_suggests_character() {
+        let err = Parser::default().query("Zeat,10\nDMG Cap,15").unwrap_err();
+
+        assert_eq!(err.expected, "character name");
+        assert_eq!(err.suggestion.as_deref(), Some("Zeta"));
+    }
+
+    #[test]
+    fn reads_example_files() {
+        let mut parser = Parser::default();
+
+        let sigils: Vec<Sigil> = parser.read_file("example_data/sigils.csv").unwrap();
+        let query: SearchQuery = parser.read_file("example_data/query.csv").unwrap();
+
+        assert_eq!(sigils[1].trait1, Trait::new(TK::Gamma, 15));
+        assert_eq!(query.sigil_slots, 12);
+    }
+
+    #[test]
+    fn read_errors_name_the_file() {
+        let err = Parser::default()
+            .read_file::<Vec<Sigil>>("example_data/missing.csv")
+            .unwrap_err();
+
+        assert!(matches!(err, LoadError::Read { .. }));
+        assert!(err
+            .to_string()
+            .starts_with("could not read example_data/missing.csv: "));
+    }
+
+    #[test]
+    fn wanted_traits() {
+        let mut parser = Parser::default();
+
+        assert_eq!(
+            parser.wanted_trait("DMG Cap=55").unwrap(),
+            Trait::new(TK::DMGCap, 55)
+        );
+        assert_eq!(
+            parser.wanted_trait(" crit rate = 30").unwrap(),
+            Trait::new(TK::CriticalHitRate, 30)
+        );
+
+        let err = parser.wanted_trait("Gamma 30").unwrap_err();
+        assert_eq!((err.column, err.expected.as_str()), (9, "`=`"));
+
+        let err = parser.wanted_trait("Gama= x").unwrap_err();
+        assert_eq!(err.suggestion.as_deref(), Some("Gamma"));
+
+        let err = parser.wanted_trait("Gamma= x").unwrap_err();
+        assert_eq!((err.column, err.text.as_str()), (8, "x"));
+
+        assert_eq!(parser.trait_name(" enmity").unwrap(), TK::Enmity);
+        assert_eq!(parser.trait_name("  ").unwrap_err().column, 3);
+    }
+
+    #[test]
+    fn item_numbers() {
+        let parser = Parser::default();
+
+        assert_eq!(parser.item_number(" 3", 3).unwrap(), 2);
+
+        for text in ["0", "4", "x"] {
+            let err = parser.item_number(text, 3).unwrap_err();
+            assert_eq!(err.expected, "an item number from 1 to 3");
+            assert_eq!(err.text, text);
+        }
+
+        let numbers = ["2".to_string(), "1".to_string(), " 2".to_string()];
+        assert_eq!(parser.item_numbers(&numbers[..2], 3).unwrap(), [1, 0]);
+        let err = parser.item_numbers(&numbers, 3).unwrap_err();
+        assert_eq!(err.expected, "an item number not given before");
+    }
+}
@@ -0,0 +1,343 @@
+use std::borrow::Cow;
+
+use nom::{
+    branch::alt,
+    bytes::complete::{is_not, tag, take_till},
+    character::complete::{char, space0},
+    combinator::{cut, map, value},
+    error::{context, VerboseError},
+    multi::fold_many0,
+    sequence::{delimited, preceded, terminated},
+    IResult,
+};
+
+use super::error::{innermost_context, ParseError};
+
+type ParseResult<'a, T> = IResult<&'a str, T, VerboseError<&'a str>>;
+
+/// a column of an input table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Column {
+    Name,
+    /// the name of the n-th trait, starting at 1
+    Trait(u8),
+    /// the level of the n-th trait, starting at 1
+    Level(u8),
+    Quantity,
+    /// the weight of an optional trait of a query
+    Weight,
+    /// the highest level a query allows for a trait
+    Max,
+}
+
+/// the columns of a kind of table
+pub(super) struct Layout {
+    /// the columns in the order used when the table has no header row
+    pub columns: &'static [Column],
+    /// the columns a header row has to name
+    pub required: &'static [Column],
+}
+
+/// one field of a line, without surrounding quotes and spaces
+#[derive(Debug)]
+pub(super) struct Field<'a> {
+    pub text: Cow<'a, str>,
+    /// the column the field starts at in characters, starting at 1
+    pub column: usize,
+}
+
+/// a line of a table with its fields assigned to columns
+#[derive(Debug)]
+pub(super) struct Row<'a> {
+    pub line: usize,
+    /// the column just past the end of the line, where missing fields are reported
+    end_column: usize,
+    cells: Vec<(Column, Field<'a>)>,
+}
+
+impl Column {
+    fn from_name(name: &str) -> Option<Column> {
+        let name: String = name
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '_')
+            .flat_map(char::to_lowercase)
+            .collect();
+
+        match name.as_str() {
+            "name" | "weapon" => Some(Column::Name),
+            "trait" | "trait1" => Some(Column::Trait(1)),
+            "trait2" => Some(Column::Trait(2)),
+            "trait3" => Some(Column::Trait(3)),
+            "level" | "level1" => Some(Column::Level(1)),
+            "level2" => Some(Column::Level(2)),
+            "level3" => Some(Column::Level(3)),
+            "quantity" | "count" => Some(Column::Quantity),
+            "weight" => Some(Column::Weight),
+            "max" | "maxlevel" => Some(Column::Max),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> String {
+        match self {
+            Column::Name => "name".to_string(),
+            Column::Trait(n) => format!("trait {n}"),
+            Column::Level(n) => format!("level {n}"),
+            Column::Quantity => "quantity".to_string(),
+            Column::Weight => "weight".to_string(),
+            Column::Max => "max level".to_string(),
+        }
+    }
+}
+
+impl<'a> Row<'a> {
+    /// the field in `column`, unless it is missing or empty
+    pub fn get(&self, column: Column) -> Option<&Field<'a>> {
+        self.cells
+            .iter()
+            .find(|(c, field)| *c == column && !field.text.is_empty())
+            .map(|(_, field)| field)
+    }
+
+    /// the error for a field that has to be filled in, pointing at the empty field
+    /// or at the end of the line if the line has no such field
+    pub fn missing(&self, column: Column, expected: &'static str) -> ParseError {
+        let position = self
+            .cells
+            .iter()
+            .find(|(c, _)| *c == column)
+            .map_or(self.end_column, |(_, field)| field.column);
+        ParseError::new(self.line, position, expected, "")
+    }
+}
+
+/// the lines of `input` with their line numbers, skipping blank lines and `#` comments
+pub(super) fn content_lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| {
+            let line = line.trim_start();
+            !line.is_empty() && !line.starts_with('#')
+        })
+}
+
+/// assigns the fields of every line to the columns of `layout`. if the first line names
+/// columns, it is a header row that gives the order of the columns in the lines below
+pub(super) fn rows<'a>(
+    mut lines: impl Iterator<Item = (usize, &'a str)>,
+    layout: &Layout,
+) -> Result<Vec<Row<'a>>, ParseError> {
+    let Some((first_number, first_line)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let first = split_fields(first_number, first_line)?;
+
+    let is_header = first
+        .first()
+        .is_some_and(|field| Column::from_name(&field.text).is_some());
+    let (columns, first_row) = if is_header {
+        (
+            header_columns(first_number, first_line, &first, layout)?,
+            None,
+        )
+    } else {
+        (
+            layout.columns.to_vec(),
+            Some((first_number, first_line, first)),
+        )
+    };
+
+    let mut rows = Vec::new();
+    if let Some((number, line, fields)) = first_row {
+        rows.push(row(number, line, fields, &columns)?);
+    }
+    for (number, line) in lines {
+        let fields = split_fields(number, line)?;
+        rows.push(row(number, line, fields, &columns)?);
+    }
+
+    Ok(rows)
+}
+
+fn header_columns(
+    line_number: usize,
+    line: &str,
+    header: &[Field],
+    layout: &Layout,
+) -> Result<Vec<Column>, ParseError> {
+    let mut columns = Vec::with_capacity(header.len());
+    for field in header {
+        match Column::from_name(&field.text).filter(|c| layout.columns.contains(c)) {
+            Some(column) if !columns.contains(&column) => columns.push(column),
+            _ => {
+                return Err(ParseError::new(
+                    line_number,
+                    field.column,
+                    "column name",
+                    field.text.as_ref(),
+                ))
+            }
+        }
+    }
+
+    if let Some(missing) = layout.required.iter().find(|c| !columns.contains(c)) {
+        let end_column = line.chars().count() + 1;
+        return Err(ParseError::new(
+            line_number,
+            end_column,
+            format!("a `{}` column", missing.name()),
+            "",
+        ));
+    }
+
+    Ok(columns)
+}
+
+fn row<'a>(
+    line_number: usize,
+    line: &'a str,
+    fields: Vec<Field<'a>>,
+    columns: &[Column],
+) -> Result<Row<'a>, ParseError> {
+    if let Some(extra) = fields.get(columns.len()) {
+        return Err(ParseError::new(
+            line_number,
+            extra.column,
+            "end of line",
+            extra.text.as_ref(),
+        ));
+    }
+
+    Ok(Row {
+        line: line_number,
+        end_column: line.chars().count() + 1,
+        cells: columns.iter().copied().zip(fields).collect(),
+    })
+}
+
+/// splits a line into its comma separated fields. a field can be put in double quotes
+/// to contain commas, with `""` standing for a quote inside of it
+pub(super) fn split_fields(line_number: usize, line: &str) -> Result<Vec<Field<'_>>, ParseError> {
+    let mut fields = Vec::new();
+    let mut rest = line;
+
+    loop {
+        let (after, text) = field(rest).map_err(|err| {
+            let (position, expected) = match err {
+                nom::Err::Error(e) | nom::Err::Failure(e) => innermost_context(e),
+                nom::Err::Incomplete(_) => (None, "more input"),
+            };
+            let position = position.unwrap_or(rest);
+            ParseError::new(
+                line_number,
+                column_of(line, position),
+                expected,
+                position.trim(),
+            )
+        })?;
+
+        let start = rest.len() - rest.trim_start().len();
+        fields.push(Field {
+            column: column_of(line, &rest[start..]),
+            text,
+        });
+
+        if after.is_empty() {
+            return Ok(fields);
+        }
+        match char::<_, nom::error::Error<&str>>(',')(after) {
+            Ok((after, _)) => rest = after,
+            Err(_) => {
+                let text = after.split(',').next().unwrap_or_default().trim();
+                return Err(ParseError::new(
+                    line_number,
+                    column_of(line, after),
+                    "`,`",
+                    text,
+                ));
+            }
+        }
+    }
+}
+
+fn field(input: &str) -> ParseResult<'_, Cow<'_, str>> {
+    alt((
+        map(quoted, Cow::Owned),
+        map(take_till(|c| c == ','), |s: &str| Cow::Borrowed(s.trim())),
+    ))(input)
+}
+
+fn quoted(input: &str) -> ParseResult<'_, String> {
+    delimited(
+        preceded(space0, char('"')),
+        fold_many0(
+            alt((is_not("\""), value("\"", tag("\"\"")))),
+            String::new,
+            |mut text, part| {
+                text.push_str(part);
+                text
+            },
+        ),
+        cut(context("closing `\"`", terminated(char('"'), space0))),
+    )(input)
+}
+
+/// the column in characters at which `rest`, a suffix of `line`, starts
+fn column_of(line: &str, rest: &str) -> usize {
+    line[..line.len() - rest.len()].chars().count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::TraitKind;
+
+    use super::*;
+
+    fn texts(line: &str) -> Vec<String> {
+        split_fields(1, line)
+            .unwrap()
+            .into_iter()
+            .map(|field| field.text.into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn splits_quoted_fields() {
+        assert_eq!(texts("a, b ,"), ["a", "b", ""]);
+        assert_eq!(
+            texts(r#""Sword, Great",ATK,"say ""hi""""#),
+            ["Sword, Great", "ATK", r#"say "hi""#]
+        );
+    }
+
+    #[test]
+    fn unterminated_quote() {
+        let err = split_fields(3, r#"ATK,"Sword"#).unwrap_err();
+
+        assert_eq!((err.line, err.column), (3, 11));
+        assert_eq!(err.expected, "closing `\"`");
+    }
+
+    #[test]
+    fn text_after_quote() {
+        let err = split_fields(1, r#""ATK"x,15"#).unwrap_err();
+
+        assert_eq!(err.column, 6);
+        assert_eq!(err.text, "x");
+    }
+
+    #[test]
+    fn column_names() {
+        // a header row must never be mistaken for a line of data
+        for name in [
+            "name", "weapon", "trait", "level", "quantity", "count", "weight",
+        ] {
+            assert_eq!(TraitKind::from_name(name), None);
+        }
+        assert_eq!(Column::from_name("Trait 2"), Some(Column::Trait(2)));
+        assert_eq!(Column::from_name("level_1"), Some(Column::Level(1)));
+    }
+}
@@ -0,0 +1,213 @@
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use crate::model::{SearchQuery, Sigil, Trait, Wrightstone};
+
+/// writes the contents of an input file in the format read by [`super::Parser`]
+pub trait ToCsv {
+    fn to_csv(&self) -> String;
+}
+
+/// one line per run of identical sigils, with the quantity column filled in for runs of
+/// more than one sigil
+impl ToCsv for [Sigil] {
+    fn to_csv(&self) -> String {
+        stacked_lines(self, |sigil| {
+            format!("{},{}", fields(sigil.trait1), optional_fields(sigil.trait2))
+        })
+    }
+}
+
+/// one line per run of identical wrightstones, with the quantity column filled in for runs
+/// of more than one wrightstone
+impl ToCsv for [Wrightstone] {
+    fn to_csv(&self) -> String {
+        stacked_lines(self, |wrightstone| {
+            format!(
+                "{},{},{}",
+                fields(wrightstone.trait1),
+                optional_fields(wrightstone.trait2),
+                optional_fields(wrightstone.trait3)
+            )
+        })
+    }
+}
+
+/// the character and number of sigil slots, followed by the desired traits sorted by kind,
+/// the optional traits with their weights and the max levels
+impl ToCsv for SearchQuery {
+    fn to_csv(&self) -> String {
+        let mut csv = match self.character {
+            Some(character) => format!("{},{}\n", character.name(), self.sigil_slots),
+            None => format!("{}\n", self.sigil_slots),
+        };
+
+        for (&kind, &level) in self.desired_traits.iter().sorted_by_key(|(&kind, _)| kind) {
+            // levels are read one byte per line and added up, so high levels take several lines
+            let mut rest = level;
+            loop {
+                let line_level = rest.min(u8::MAX.into());
+                writeln!(csv, "{},{line_level}", kind.name()).unwrap();
+                rest -= line_level;
+                if rest == 0 {
+                    break;
+                }
+            }
+        }
+
+        for t in &self.optional_traits {
+            writeln!(csv, "{},{},{}", t.kind.name(), t.level, t.weight).unwrap();
+        }
+
+        for (&kind, &max) in self.max_levels.iter().sorted_by_key(|(&kind, _)| kind) {
+            writeln!(csv, "{},,,{max}", kind.name()).unwrap();
+        }
+
+        csv
+    }
+}
+
+fn stacked_lines<T: PartialEq>(items: &[T], line: impl Fn(&T) -> String) -> String {
+    let mut csv = String::new();
+
+    for (count, item) in items.iter().dedup_with_count() {
+        let line = line(item);
+        let mut rest = count;
+        while rest > 0 {
+            let quantity = rest.min(u16::MAX.into());
+            if quantity == 1 {
+                writeln!(csv, "{line}").unwrap();
+            } else {
+                writeln!(csv, "{line},{quantity}").unwrap();
+            }
+            rest -= quantity;
+        }
+    }
+
+    csv
+}
+
+fn fields(t: Trait) -> String {
+    format!("{},{}", t.kind.name(), t.level)
+}
+
+fn optional_fields(t: Option<Trait>) -> String {
+    t.map_or_else(|| ",".to_string(), fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Character, OptionalTrait, TraitKind, TraitSet};
+    use proptest::prelude::*;
+    use proptest::sample::select;
+
+    use super::*;
+    use crate::io::Parser;
+
+    fn any_trait() -> impl Strategy<Value = Trait> {
+        let kinds: Vec<TraitKind> = TraitKind::all().collect();
+        (select(kinds), any::<u8>()).prop_map(|(kind, level)| Trait::new(kind, level))
+    }
+
+    fn any_sigil() -> impl Strategy<Value = Sigil> {
+        (any_trait(), proptest::option::of(any_trait()))
+            .prop_map(|(trait1, trait2)| Sigil { trait1, trait2 })
+    }
+
+    fn any_wrightstone() -> impl Strategy<Value = Wrightstone> {
+        let optional = || proptest::option::of(any_trait());
+        (any_trait(), optional(), optional()).prop_map(|(trait1, trait2, trait3)| Wrightstone {
+            trait1,
+            trait2,
+            trait3,
+        })
+    }
+
+    fn any_query() -> impl Strategy<Value = SearchQuery> {
+        let traits = proptest::collection::vec(any_trait(), 0..8);
+        let character = proptest::option::of(select(Character::ALL.to_vec()));
+        let optional = (any_trait(), any::<u16>(), any::<u16>()).prop_map(|(t, level, weight)| {
+            OptionalTrait {
+                kind: t.kind,
+                level,
+                weight,
+            }
+        });
+        let optional_traits = proptest::collection::vec(optional, 0..4);
+        let max_levels = proptest::collection::vec((any_trait(), any::<u16>()), 0..4);
+        (traits, any::<u8>(), character, optional_traits, max_levels).prop_map(
+            |(traits, sigil_slots, character, optional_traits, max_levels)| {
+                let mut query = SearchQuery {
+                    desired_traits: traits.into_iter().collect(),
+                    sigil_slots,
+                    character,
+                    optional_traits,
+                    ..Default::default()
+                };
+                for (t, max) in max_levels {
+                    query.max_levels.insert(t.kind, max);
+                }
+                query
+            },
+        )
+    }
+
+    /// repeats some items so that the writer has runs of identical items to stack
+    fn with_runs<T: Clone + std::fmt::Debug>(
+        item: impl Strategy<Value = T>,
+    ) -> impl Strategy<Value = Vec<T>> {
+        proptest::collection::vec((item, 1..4usize), 0..16).prop_map(|runs| {
+            runs.into_iter()
+                .flat_map(|(item, count)| std::iter::repeat_n(item, count))
+                .collect()
+        })
+    }
+
+    #[test]
+    fn writes_example_format() {
+        let sigils = [
+            Sigil::new_single(Trait::new(TraitKind::Aegis, 15)),
+            Sigil::new_single(Trait::new(TraitKind::Aegis, 15)),
+            Sigil {
+                trait1: Trait::new(TraitKind::Alpha, 12),
+                trait2: Some(Trait::new(TraitKind::DMGCap, 12)),
+            },
+        ];
+
+        assert_eq!(sigils.to_csv(), "Aegis,15,,,2\nAlpha,12,DMG Cap,12\n");
+    }
+
+    #[test]
+    fn query_splits_high_levels() {
+        let query = SearchQuery {
+            desired_traits: TraitSet::from_iter([
+                Trait::new(TraitKind::DMGCap, 200),
+                Trait::new(TraitKind::DMGCap, 100),
+            ]),
+            sigil_slots: 12,
+            character: Some(Character::Zeta),
+            ..Default::default()
+        };
+
+        assert_eq!(query.to_csv(), "Zeta,12\nDMG Cap,255\nDMG Cap,45\n");
+    }
+
+    proptest! {
+        #[test]
+        fn sigils_round_trip(sigils in with_runs(any_sigil())) {
+            prop_assert_eq!(Parser::default().sigils(&sigils.to_csv()).unwrap(), sigils);
+        }
+
+        #[test]
+        fn wrightstones_round_trip(wrightstones in with_runs(any_wrightstone())) {
+            let parsed = Parser::default().wrightstones(&wrightstones.to_csv()).unwrap();
+            prop_assert_eq!(parsed, wrightstones);
+        }
+
+        #[test]
+        fn query_round_trips(query in any_query()) {
+            prop_assert_eq!(Parser::default().query(&query.to_csv()).unwrap(), query);
+        }
+    }
+}
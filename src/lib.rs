@@ -1,2 +1,4 @@
+pub mod io;
 pub mod model;
+pub mod report;
 pub mod solver;
@@ -1,57 +1,271 @@
-use gbfr_build_calculator::model::SearchPool;
-use gbfr_build_calculator::solver::{GeneticSolver, Solver};
-use parser::{parse_sigils, parse_wrightstones};
-
-use crate::parser::parse_query;
-
-mod parser;
-
-fn main() {
-    let sigils = std::fs::read_to_string("example_data/sigils.csv").unwrap();
-    let (_, sigils) = parse_sigils(&sigils).unwrap();
-    // dbg!(&sigils);
-
-    let wrightstones = std::fs::read_to_string("example_data/wrightstones.csv").unwrap();
-    let (_, wrightstones) = parse_wrightstones(&wrightstones).unwrap();
-    // dbg!(&wrightstones);
-
-    let query = std::fs::read_to_string("example_data/query.csv").unwrap();
-    let (_, query) = parse_query(&query).unwrap();
-    // dbg!(&query);
-
-    let pool = SearchPool {
-        sigils,
-        wrightstones,
-    };
-
-    // let mut results = NaiveSolver.search(pool, &query);
-    // results.sort_by_cached_key(|res| res.total_trait_level());
-
-    // for (i, result) in results.iter().enumerate() {
-    //     println!("Result {}", i + 1);
-    //     println!("\nSigils:");
-    //     for sigil in &result.sigils {
-    //         println!("{}", sigil);
-    //     }
-    //     println!("\nWrightstone:");
-    //     if let Some(stone) = &result.wrightstone {
-    //         println!("{}", stone);
-    //     } else {
-    //         println!("None")
-    //     }
-    //     println!("\nTotal Trait Level: {}", result.total_trait_level());
-    //     println!("\n\n\n");
-    // }
-
-    // println!("{} results found.", results.len());
-
-    let solver = GeneticSolver {
-        population_size: 100,
-        generations: 1,
-        tournament_size: 2,
-        crossover_rate: 0.6,
-        mutation_rate: 0.05,
-    };
-    let results = solver.search(pool, &query);
-    todo!()
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::process::ExitCode;
+
+use clap::Parser as _;
+
+use gbfr_build_calculator::io::{InputFile, LoadError, Parser};
+use gbfr_build_calculator::model::{
+    SearchPool, SearchQuery, SearchResult, Sigil, TraitSet, Wrightstone,
+};
+use gbfr_build_calculator::report::{self, BuildReport};
+use gbfr_build_calculator::solver::{
+    self, BranchBoundSolver, GeneticSolver, NaiveSolver, Objective, Solver,
+};
+
+use cli::{Cli, Command, InputArgs, OutputFormat, QueryArgs, SearchArgs, SolverKind};
+
+mod cli;
+
+/// exit code when the search ran but no build satisfies the query
+const NO_RESULTS: u8 = 1;
+
+/// exit code when the input files cannot be read, matching clap's code for invalid arguments
+const INVALID_INPUT: u8 = 2;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Search(args) => search(&args),
+        Command::Diagnose(args) => diagnose(&args),
+    }
+}
+
+fn search(args: &SearchArgs) -> ExitCode {
+    let (pool, query) = match load_inputs(&args.input) {
+        Ok(inputs) => inputs,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::from(INVALID_INPUT);
+        }
+    };
+
+    let solver: Box<dyn Solver> = match args.solver {
+        SolverKind::Naive => Box::new(NaiveSolver),
+        SolverKind::BranchBound => Box::new(BranchBoundSolver),
+        SolverKind::Genetic => Box::new(GeneticSolver::from(&args.genetic)),
+    };
+    let objective = match args.best {
+        Some(objective) => Some(Objective::from(objective)),
+        None if !query.optional_traits.is_empty() => Some(Objective::OptionalTraits),
+        None => None,
+    };
+    let mut proven = None;
+    let mut fewest = None;
+    let count = match objective {
+        Some(Objective::FreeSlots) => {
+            let smallest = solver.search_fewest_sigils(pool, &query, args.limit);
+            proven = Some(smallest.proven);
+            fewest = smallest.results.first().map(|result| result.sigils.len());
+            print_results(smallest.results, &query, args.format)
+        }
+        Some(objective) => {
+            let results = solver.search_best(pool, &query, objective, args.limit);
+            print_results(results, &query, args.format)
+        }
+        None => stream_results(solver.as_ref(), pool, &query, args.limit, args.format),
+    };
+
+    match (proven, fewest) {
+        (Some(true), Some(fewest)) => eprintln!("no build with fewer than {fewest} sigils exists"),
+        (Some(false), Some(_)) => eprintln!(
+            "builds with fewer sigils may exist, only the exact solvers can rule them out"
+        ),
+        _ => {}
+    }
+
+    if count > 0 {
+        ExitCode::SUCCESS
+    } else {
+        eprintln!("no build satisfies the query, the `diagnose` command explains why");
+        ExitCode::from(NO_RESULTS)
+    }
+}
+
+fn diagnose(args: &InputArgs) -> ExitCode {
+    let (pool, query) = match load_inputs(args) {
+        Ok(inputs) => inputs,
+        Err(err) => {
+            eprintln!("{err}");
+            return ExitCode::from(INVALID_INPUT);
+        }
+    };
+
+    let diagnosis = solver::diagnose(pool, &query);
+    println!("{diagnosis}\n");
+    print!(
+        "{}",
+        report::text(&[BuildReport::new(diagnosis.closest, &query)])
+    );
+
+    ExitCode::SUCCESS
+}
+
+fn load_inputs(args: &InputArgs) -> Result<(SearchPool, SearchQuery), LoadError> {
+    let mut parser = Parser {
+        fuzzy: args.fuzzy,
+        ..Default::default()
+    };
+
+    let sigils: Vec<Sigil> = load(&mut parser, &args.sigils)?;
+    let wrightstones: Vec<Wrightstone> = match &args.wrightstones {
+        Some(path) => load(&mut parser, path)?,
+        None => Vec::new(),
+    };
+    let weapons = match &args.weapons {
+        Some(path) => load(&mut parser, path)?,
+        None => Vec::new(),
+    };
+    let mut query = query(&mut parser, &args.query)?;
+    lock(&parser, &mut query, &args.query, &sigils, &wrightstones)?;
+
+    Ok((SearchPool::new(sigils, wrightstones, weapons), query))
+}
+
+/// the query file, if any, with the traits and slots given on the command line applied
+fn query(parser: &mut Parser, args: &QueryArgs) -> Result<SearchQuery, LoadError> {
+    let mut query = match &args.file {
+        Some(path) => load(parser, path)?,
+        None => SearchQuery::default(),
+    };
+
+    let mut wanted = TraitSet::new();
+    for want in &args.want {
+        let wanted_trait = parser
+            .wanted_trait(want)
+            .map_err(|err| LoadError::Parse(err.in_file("--want")))?;
+        wanted.add(wanted_trait);
+    }
+    for correction in parser.corrections.drain(..) {
+        eprintln!("--want:{correction}");
+    }
+    for (&kind, &level) in wanted.iter() {
+        query.desired_traits.insert(kind, level);
+    }
+
+    for exclude in &args.exclude {
+        let kind = parser
+            .trait_name(exclude)
+            .map_err(|err| LoadError::Parse(err.in_file("--exclude")))?;
+        query.max_levels.insert(kind, 0);
+    }
+    for correction in parser.corrections.drain(..) {
+        eprintln!("--exclude:{correction}");
+    }
+    for max in &args.max {
+        let max = parser
+            .wanted_trait(max)
+            .map_err(|err| LoadError::Parse(err.in_file("--max")))?;
+        query.max_levels.insert(max.kind, max.level.into());
+    }
+    for correction in parser.corrections.drain(..) {
+        eprintln!("--max:{correction}");
+    }
+
+    if let Some(slots) = args.slots {
+        query.sigil_slots = slots;
+    }
+    if args.allow_empty_slots {
+        query.allow_empty_slots = true;
+    }
+
+    Ok(query)
+}
+
+/// locks the sigils and wrightstone picked by their numbers in the inventory files
+fn lock(
+    parser: &Parser,
+    query: &mut SearchQuery,
+    args: &QueryArgs,
+    sigils: &[Sigil],
+    wrightstones: &[Wrightstone],
+) -> Result<(), LoadError> {
+    let locked = parser
+        .item_numbers(&args.lock_sigil, sigils.len())
+        .map_err(|err| LoadError::Parse(err.in_file("--lock-sigil")))?;
+    for i in locked {
+        query.locked_sigils.push(sigils[i].clone());
+    }
+    if let Some(number) = &args.lock_wrightstone {
+        let i = parser
+            .item_number(number, wrightstones.len())
+            .map_err(|err| LoadError::Parse(err.in_file("--lock-wrightstone")))?;
+        query.locked_wrightstone = Some(wrightstones[i].clone());
+    }
+
+    Ok(())
+}
+
+/// reads and parses the file at `path`, reporting names corrected in fuzzy mode
+fn load<T: InputFile>(parser: &mut Parser, path: &Path) -> Result<T, LoadError> {
+    let parsed = parser.read_file(path)?;
+
+    for correction in parser.corrections.drain(..) {
+        eprintln!("{}:{correction}", path.display());
+    }
+
+    Ok(parsed)
+}
+
+/// prints the builds and returns how many there were
+fn print_results(results: Vec<SearchResult>, query: &SearchQuery, format: OutputFormat) -> usize {
+    let count = results.len();
+    let reports: Vec<BuildReport> = results
+        .into_iter()
+        .map(|result| BuildReport::new(result, query))
+        .collect();
+
+    match format {
+        OutputFormat::Text => {
+            print!("{}", report::text(&reports));
+            println!("{count} results found.");
+        }
+        OutputFormat::Markdown => print!("{}", report::markdown(&reports)),
+        #[cfg(feature = "serde")]
+        OutputFormat::Json => println!("{}", report::json(&reports)),
+    }
+
+    count
+}
+
+/// prints each build as soon as the solver finds it, stopping after `limit` builds,
+/// and returns how many there were
+fn stream_results(
+    solver: &dyn Solver,
+    pool: SearchPool,
+    query: &SearchQuery,
+    limit: usize,
+    format: OutputFormat,
+) -> usize {
+    let mut count = 0;
+    // a JSON array is only complete after the last build, so those reports are collected
+    #[cfg(feature = "serde")]
+    let mut json = Vec::new();
+
+    solver.search_each(pool, query, &mut |result| {
+        count += 1;
+        let report = BuildReport::new(result, query);
+        match format {
+            OutputFormat::Text => print!("{}", report::text_build(count, &report)),
+            OutputFormat::Markdown => print!("{}", report::markdown_build(count, &report)),
+            #[cfg(feature = "serde")]
+            OutputFormat::Json => json.push(report),
+        }
+
+        if count < limit {
+            ControlFlow::Continue(())
+        } else {
+            ControlFlow::Break(())
+        }
+    });
+
+    match format {
+        OutputFormat::Text => println!("{count} results found."),
+        OutputFormat::Markdown => {}
+        #[cfg(feature = "serde")]
+        OutputFormat::Json => println!("{}", report::json(&json)),
+    }
+
+    count
 }
@@ -0,0 +1,120 @@
+use std::{fmt::Display, str::FromStr};
+
+use super::names::{closest, normalize};
+
+/// the playable characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Character {
+    /// Gran or Djeeta
+    Captain,
+    Katalina,
+    Rackam,
+    Io,
+    Eugen,
+    Rosetta,
+    Ferry,
+    Lancelot,
+    Vane,
+    Percival,
+    Siegfried,
+    Charlotta,
+    Yodarha,
+    Narmaya,
+    Ghandagoza,
+    Zeta,
+    Vaseraga,
+    Cagliostro,
+    Id,
+    Seofon,
+    Tweyen,
+    Sandalphon,
+}
+
+impl Character {
+    pub const ALL: [Character; 22] = {
+        use Character::*;
+        [
+            Captain, Katalina, Rackam, Io, Eugen, Rosetta, Ferry, Lancelot, Vane, Percival,
+            Siegfried, Charlotta, Yodarha, Narmaya, Ghandagoza, Zeta, Vaseraga, Cagliostro, Id,
+            Seofon, Tweyen, Sandalphon,
+        ]
+    };
+
+    pub fn name(self) -> &'static str {
+        use Character::*;
+        match self {
+            Captain => "Captain",
+            Katalina => "Katalina",
+            Rackam => "Rackam",
+            Io => "Io",
+            Eugen => "Eugen",
+            Rosetta => "Rosetta",
+            Ferry => "Ferry",
+            Lancelot => "Lancelot",
+            Vane => "Vane",
+            Percival => "Percival",
+            Siegfried => "Siegfried",
+            Charlotta => "Charlotta",
+            Yodarha => "Yodarha",
+            Narmaya => "Narmaya",
+            Ghandagoza => "Ghandagoza",
+            Zeta => "Zeta",
+            Vaseraga => "Vaseraga",
+            Cagliostro => "Cagliostro",
+            Id => "Id",
+            Seofon => "Seofon",
+            Tweyen => "Tweyen",
+            Sandalphon => "Sandalphon",
+        }
+    }
+
+    /// finds the character whose name is closest to `name`,
+    /// if it is close enough to be a likely misspelling
+    pub fn closest(name: &str) -> Option<Character> {
+        closest(name, Character::ALL.into_iter().map(|c| (c.name(), c)))
+    }
+
+    /// the number of sigil slots available to the character
+    pub fn sigil_slots(self) -> u8 {
+        12
+    }
+}
+
+impl Display for Character {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Character {
+    type Err = &'static str;
+
+    /// matches names ignoring case and surrounding spaces
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = normalize(s);
+        match s.as_str() {
+            "gran" | "djeeta" => Ok(Character::Captain),
+            _ => Character::ALL
+                .into_iter()
+                .find(|c| normalize(c.name()) == s)
+                .ok_or("invalid character name"),
+        }
+    }
+}
+
+/// characters are stored by name
+#[cfg(feature = "serde")]
+impl serde::Serialize for Character {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Character {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse()
+            .map_err(|_| serde::de::Error::custom(format!("invalid character name `{name}`")))
+    }
+}
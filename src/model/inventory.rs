@@ -0,0 +1,49 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// a number of identical items in the inventory
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stack<T> {
+    pub item: T,
+    pub count: u16,
+}
+
+impl<T> Stack<T> {
+    pub fn new(item: T, count: u16) -> Self {
+        Stack { item, count }
+    }
+}
+
+/// groups identical items into stacks, in the order each item first appears
+pub fn stack_items<T: Clone + Eq + Hash>(items: impl IntoIterator<Item = T>) -> Vec<Stack<T>> {
+    let mut stacks: Vec<Stack<T>> = Vec::new();
+    let mut positions: HashMap<T, usize> = HashMap::new();
+
+    for item in items {
+        match positions.get(&item) {
+            Some(&i) => stacks[i].count += 1,
+            None => {
+                positions.insert(item.clone(), stacks.len());
+                stacks.push(Stack::new(item, 1));
+            }
+        }
+    }
+
+    stacks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacks_identical_items_in_order() {
+        let stacks = stack_items(["b", "a", "b", "c", "b"]);
+
+        assert_eq!(
+            stacks,
+            [Stack::new("b", 3), Stack::new("a", 1), Stack::new("c", 1)]
+        );
+    }
+}
@@ -1,9 +1,18 @@
+mod character;
+mod inventory;
+mod names;
 mod search;
 mod sigil;
+mod trait_info;
 mod traits;
+mod weapon;
 mod wrightstone;
 
-pub use search::{SearchPool, SearchQuery, SearchResult};
+pub use character::Character;
+pub use inventory::{stack_items, Stack};
+pub use search::{OptionalTrait, SearchPool, SearchQuery, SearchResult};
 pub use sigil::Sigil;
+pub use trait_info::{TraitCategory, TraitInfo};
 pub use traits::{Trait, TraitKind, TraitSet};
+pub use weapon::Weapon;
 pub use wrightstone::Wrightstone;
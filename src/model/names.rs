@@ -0,0 +1,109 @@
+//! matching of names typed by users against the names used in game
+
+/// the form names are compared in, so that case, spaces and apostrophes do not matter
+pub(crate) fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace() && *c != '\'')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// the value of the candidate name closest to `name`,
+/// if it is close enough to be a likely misspelling
+pub(crate) fn closest<'c, T>(
+    name: &str,
+    candidates: impl Iterator<Item = (&'c str, T)>,
+) -> Option<T> {
+    let name = normalize(name);
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    candidates
+        .map(|(candidate, value)| (edit_distance(&name, &normalize(candidate)), value))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, value)| value)
+}
+
+/// the number of single character insertions, deletions, substitutions and swaps of
+/// adjacent characters to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // distances[i][j] is the distance between the first i characters of a and first j of b
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{TraitInfo, TraitKind as TK};
+
+    use super::*;
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("zeat", "zeta"), 1);
+        assert_eq!(edit_distance("dmgcap", "dmgcap"), 0);
+    }
+
+    #[test]
+    fn names_ignore_case_spaces_and_apostrophes() {
+        assert_eq!(TK::from_name("dmgcap"), Some(TK::DMGCap));
+        assert_eq!(
+            TK::from_name("Critical Hit Rate "),
+            Some(TK::CriticalHitRate)
+        );
+        assert_eq!(TK::from_name("roll of the die"), Some(TK::RollOfTheDie));
+        assert_eq!(TK::from_name("Heros Creed"), Some(TK::HerosCreed));
+    }
+
+    #[test]
+    fn community_abbreviations() {
+        assert_eq!(TK::from_name("crit rate"), Some(TK::CriticalHitRate));
+        assert_eq!(TK::from_name("Supp DMG"), Some(TK::SupplementaryDamage));
+        assert_eq!(TK::from_name("supp dmg"), Some(TK::SupplementaryDamage));
+    }
+
+    #[test]
+    fn normalized_names_are_unambiguous() {
+        let mut names: Vec<String> = TK::all()
+            .map(TK::info)
+            .flat_map(TraitInfo::names)
+            .map(normalize)
+            .collect();
+        let count = names.len();
+        names.sort();
+        names.dedup();
+
+        assert_eq!(names.len(), count);
+    }
+
+    #[test]
+    fn closest_finds_near_misses_only() {
+        assert_eq!(TK::closest("Critcal Hit Rate"), Some(TK::CriticalHitRate));
+        assert_eq!(TK::closest("Gama"), Some(TK::Gamma));
+        assert_eq!(TK::closest("Something Else"), None);
+    }
+}
@@ -1,24 +1,159 @@
-use super::{Sigil, TraitSet, Wrightstone};
+use super::{stack_items, Character, Sigil, Stack, TraitKind, TraitSet, Weapon, Wrightstone};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SearchQuery {
     pub desired_traits: TraitSet,
     pub sigil_slots: u8,
+    /// the character the build is for, sigils with traits exclusive to
+    /// other characters are never used
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub character: Option<Character>,
+    /// traits that are nice to have. among the builds reaching the desired traits,
+    /// the ones with the highest weighted level of optional traits are preferred
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub optional_traits: Vec<OptionalTrait>,
+    /// the highest level allowed for these traits, a level of 0 forbidding the trait.
+    /// unlike desired levels, these count levels above a trait's max level
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "TraitSet::is_empty")
+    )]
+    pub max_levels: TraitSet,
+    /// sigils every build has to use, taking up sigil slots.
+    /// each is one of the pool's copies, so no build is found if the pool lacks one
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub locked_sigils: Vec<Sigil>,
+    /// the wrightstone every build has to use instead of one from the pool
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub locked_wrightstone: Option<Wrightstone>,
+    /// builds may use fewer sigils than there are slots. solvers then only return minimal
+    /// builds, which miss a desired level or optional trait level without any one of
+    /// their sigils
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "std::ops::Not::not")
+    )]
+    pub allow_empty_slots: bool,
 }
 
+/// a trait that is nice to have, worth `weight` for every level up to `level`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OptionalTrait {
+    pub kind: TraitKind,
+    pub level: u16,
+    pub weight: u16,
+}
+
+/// the inventory available to the search, with identical sigils and wrightstones stacked
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SearchPool {
-    pub sigils: Vec<Sigil>,
-    pub wrightstones: Vec<Wrightstone>,
+    pub sigils: Vec<Stack<Sigil>>,
+    pub wrightstones: Vec<Stack<Wrightstone>>,
+    pub weapons: Vec<Weapon>,
 }
 
-#[derive(Debug, Clone)]
+/// a build found by a solver.
+/// results built with [`SearchResult::new`] keep their sigils in a canonical order,
+/// so builds using the same sigils compare and hash as equal
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SearchResult {
     pub sigils: Vec<Sigil>,
+    /// the wrightstone imbued into the weapon
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub wrightstone: Option<Wrightstone>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub weapon: Option<Weapon>,
+}
+
+impl SearchPool {
+    /// stacks identical sigils and wrightstones, counting wrightstones with the same traits
+    /// in a different order as identical
+    pub fn new(sigils: Vec<Sigil>, wrightstones: Vec<Wrightstone>, weapons: Vec<Weapon>) -> Self {
+        SearchPool {
+            sigils: stack_items(sigils),
+            wrightstones: stack_items(wrightstones.into_iter().map(Wrightstone::canonical)),
+            weapons,
+        }
+    }
+
+    /// the number of sigils including every copy
+    pub fn sigil_count(&self) -> usize {
+        self.sigils.iter().map(|stack| stack.count as usize).sum()
+    }
+}
+
+impl SearchQuery {
+    /// whether the query asks for the trait, either as desired or as optional trait
+    pub fn wants(&self, kind: TraitKind) -> bool {
+        self.desired_traits.contains(kind) || self.optional_traits.iter().any(|t| t.kind == kind)
+    }
+
+    /// the sigil slots not taken by locked sigils, `None` if more sigils are locked
+    /// than there are slots
+    pub fn unlocked_slots(&self) -> Option<usize> {
+        (self.sigil_slots as usize).checked_sub(self.locked_sigils.len())
+    }
+
+    /// the highest level allowed for the trait, if the query limits it
+    pub fn max_level(&self, kind: TraitKind) -> Option<u16> {
+        self.max_levels
+            .contains(kind)
+            .then(|| self.max_levels.level(kind))
+    }
+
+    /// whether any trait in `traits` goes above its max level in the query
+    pub fn exceeds_max_levels(&self, traits: &TraitSet) -> bool {
+        self.max_levels
+            .iter()
+            .any(|(&kind, &max)| traits.level(kind) > max)
+    }
+
+    /// the weighted level of the optional traits in `traits`.
+    /// levels above an optional trait's level or its max level are not counted
+    pub fn optional_score(&self, traits: &TraitSet) -> u32 {
+        self.optional_traits
+            .iter()
+            .map(|t| u32::from(traits.effective_level(t.kind).min(t.level)) * u32::from(t.weight))
+            .sum()
+    }
 }
 
 impl SearchResult {
+    pub fn new(
+        mut sigils: Vec<Sigil>,
+        wrightstone: Option<Wrightstone>,
+        weapon: Option<Weapon>,
+    ) -> Self {
+        sigils.sort_unstable();
+        SearchResult {
+            sigils,
+            wrightstone,
+            weapon,
+        }
+    }
+
     pub fn traits(&self) -> TraitSet {
         let mut traits = TraitSet::new();
 
@@ -39,6 +174,46 @@ impl SearchResult {
             }
         }
 
+        if let Some(weapon) = &self.weapon {
+            for t in weapon.traits() {
+                traits.add(t);
+            }
+        }
+
         traits
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Trait, TraitKind as TK};
+
+    use super::*;
+
+    #[test]
+    fn stacks_wrightstones_with_traits_in_any_order() {
+        let stone = |t2, t3| Wrightstone {
+            trait1: Trait::new(TK::DMGCap, 20),
+            trait2: Some(t2),
+            trait3: Some(t3),
+        };
+        let hp = Trait::new(TK::HP, 10);
+        let drain = Trait::new(TK::Drain, 5);
+
+        let pool = SearchPool::new(vec![], vec![stone(hp, drain), stone(drain, hp)], vec![]);
+
+        assert_eq!(pool.wrightstones.len(), 1);
+        assert_eq!(pool.wrightstones[0].count, 2);
+    }
+
+    #[test]
+    fn sigil_order_does_not_matter() {
+        let dmg_cap = Sigil::new_single(Trait::new(TK::DMGCap, 15));
+        let gamma = Sigil::new_single(Trait::new(TK::Gamma, 15));
+
+        let a = SearchResult::new(vec![dmg_cap.clone(), gamma.clone()], None, None);
+        let b = SearchResult::new(vec![gamma, dmg_cap], None, None);
+
+        assert_eq!(a, b);
+    }
+}
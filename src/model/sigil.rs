@@ -2,9 +2,14 @@ use std::fmt::Display;
 
 use super::traits::Trait;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sigil {
     pub trait1: Trait,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub trait2: Option<Trait>,
 }
 
@@ -15,6 +20,10 @@ impl Sigil {
             trait2: None,
         }
     }
+
+    pub fn traits(&self) -> impl Iterator<Item = Trait> {
+        [Some(self.trait1), self.trait2].into_iter().flatten()
+    }
 }
 
 impl Display for Sigil {
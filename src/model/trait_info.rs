@@ -0,0 +1,470 @@
+use super::names::{closest, normalize};
+use super::{Character, TraitKind};
+
+/// static information about a kind of trait
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TraitInfo {
+    pub kind: TraitKind,
+    /// the name shown in game
+    pub name: &'static str,
+    /// other accepted spellings of the name
+    pub aliases: &'static [&'static str],
+    pub category: TraitCategory,
+    /// the only character the trait has an effect for, if it is character exclusive
+    pub character: Option<Character>,
+    /// the highest level that still has an effect in game, any levels above it are wasted
+    pub max_level: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TraitCategory {
+    Offensive,
+    Defensive,
+    Utility,
+    Resistance,
+}
+
+impl TraitInfo {
+    /// the name followed by all aliases
+    pub fn names(&self) -> impl Iterator<Item = &'static str> {
+        std::iter::once(self.name).chain(self.aliases.iter().copied())
+    }
+}
+
+impl TraitKind {
+    pub fn info(self) -> &'static TraitInfo {
+        &TRAIT_INFO[self as usize]
+    }
+
+    /// every trait kind in declaration order
+    pub fn all() -> impl Iterator<Item = TraitKind> {
+        TRAIT_INFO.iter().map(|info| info.kind)
+    }
+
+    /// finds the trait kind whose name or one of its aliases matches `name`,
+    /// ignoring case, spaces and apostrophes
+    pub fn from_name(name: &str) -> Option<TraitKind> {
+        let name = normalize(name);
+        TRAIT_INFO
+            .iter()
+            .find(|info| info.names().any(|n| normalize(n) == name))
+            .map(|info| info.kind)
+    }
+
+    /// finds the trait kind whose name or alias is closest to `name`,
+    /// if it is close enough to be a likely misspelling
+    pub fn closest(name: &str) -> Option<TraitKind> {
+        closest(
+            name,
+            TRAIT_INFO
+                .iter()
+                .flat_map(|info| info.names().map(|n| (n, info.kind))),
+        )
+    }
+
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    pub fn category(self) -> TraitCategory {
+        self.info().category
+    }
+
+    /// the only character the trait has an effect for, if it is character exclusive
+    pub fn character(self) -> Option<Character> {
+        self.info().character
+    }
+
+    pub fn is_exclusive(self) -> bool {
+        self.character().is_some()
+    }
+
+    /// whether the trait has an effect for `character`
+    pub fn is_usable_by(self, character: Character) -> bool {
+        self.character().is_none_or(|c| c == character)
+    }
+
+    pub fn max_level(self) -> u16 {
+        self.info().max_level
+    }
+}
+
+/// a trait usable by every character
+const fn info(
+    kind: TraitKind,
+    name: &'static str,
+    aliases: &'static [&'static str],
+    category: TraitCategory,
+    max_level: u16,
+) -> TraitInfo {
+    TraitInfo {
+        kind,
+        name,
+        aliases,
+        category,
+        character: None,
+        max_level,
+    }
+}
+
+/// a trait that only has an effect for `character`
+const fn exclusive(
+    kind: TraitKind,
+    name: &'static str,
+    character: Character,
+    category: TraitCategory,
+    max_level: u16,
+) -> TraitInfo {
+    TraitInfo {
+        kind,
+        name,
+        aliases: &[],
+        category,
+        character: Some(character),
+        max_level,
+    }
+}
+
+/// indexed by `TraitKind` discriminant, so entries must stay in declaration order
+static TRAIT_INFO: [TraitInfo; 120] = {
+    use Character::*;
+    use TraitCategory::*;
+    use TraitKind::*;
+    [
+        info(Aegis, "Aegis", &[], Defensive, 15),
+        info(Alpha, "Alpha", &[], Offensive, 45),
+        info(
+            ATKDownResistance,
+            "ATK Down Resistance",
+            &[],
+            Resistance,
+            15,
+        ),
+        info(ATK, "ATK", &["Attack"], Offensive, 65),
+        info(Autorevive, "Autorevive", &[], Defensive, 15),
+        info(Berserker, "Berserker", &[], Offensive, 15),
+        info(Beta, "Beta", &[], Offensive, 45),
+        info(BlightResistance, "Blight Resistance", &[], Resistance, 15),
+        info(BreakAssassin, "Break Assassin", &[], Offensive, 15),
+        info(BurnResistance, "Burn Resistance", &[], Resistance, 15),
+        exclusive(ButterflysGrace, "Butterfly's Grace", Narmaya, Offensive, 15),
+        exclusive(ButterflysValor, "Butterfly's Valor", Narmaya, Offensive, 15),
+        info(Cascade, "Cascade", &[], Offensive, 15),
+        info(Catastrophe, "Catastrophe", &[], Offensive, 15),
+        info(
+            ChargedAttackDMG,
+            "Charged Attack DMG",
+            &["Charged Attack Damage", "CA DMG"],
+            Offensive,
+            30,
+        ),
+        info(ComboBooster, "Combo Booster", &[], Offensive, 45),
+        info(
+            ComboFinisherDMG,
+            "Combo Finisher DMG",
+            &["Combo Finisher Damage", "Finisher DMG"],
+            Offensive,
+            30,
+        ),
+        info(ConcentratedFire, "Concentrated Fire", &[], Offensive, 15),
+        exclusive(
+            CrabvestmentReturns,
+            "Crabvestment Returns",
+            Katalina,
+            Offensive,
+            15,
+        ),
+        exclusive(CrabbyResonance, "Crabby Resonance", Katalina, Offensive, 15),
+        exclusive(CrimsonsClout, "Crimson's Clout", Zeta, Offensive, 15),
+        exclusive(CrimsonsFlight, "Crimson's Flight", Zeta, Offensive, 15),
+        info(
+            CriticalHitDMG,
+            "Critical Hit DMG",
+            &["Critical Hit Damage", "Crit DMG", "Crit Damage"],
+            Offensive,
+            65,
+        ),
+        info(
+            CriticalHitRate,
+            "Critical Hit Rate",
+            &["Crit Rate", "Crit Hit Rate"],
+            Offensive,
+            65,
+        ),
+        info(
+            DarkflameResistance,
+            "Darkflame Resistance",
+            &[],
+            Resistance,
+            15,
+        ),
+        info(
+            DEFDownDesistance,
+            "DEF Down Resistance",
+            &[],
+            Resistance,
+            15,
+        ),
+        info(DizzyResistance, "Dizzy Resistance", &[], Resistance, 15),
+        info(DMGCap, "DMG Cap", &["Damage Cap"], Offensive, 65),
+        info(DodgePayback, "Dodge Payback", &[], Offensive, 15),
+        exclusive(
+            DragonslayersDominance,
+            "Dragonslayer's Dominance",
+            Siegfried,
+            Offensive,
+            15,
+        ),
+        exclusive(
+            DragonslayersIngenuity,
+            "Dragonslayer's Ingenuity",
+            Siegfried,
+            Offensive,
+            15,
+        ),
+        info(Drain, "Drain", &[], Defensive, 15),
+        exclusive(EbonysPoise, "Ebony's Poise", Vaseraga, Offensive, 15),
+        exclusive(EbonysPresence, "Ebony's Presence", Vaseraga, Offensive, 15),
+        info(Enmity, "Enmity", &[], Offensive, 45),
+        exclusive(EternalRagesEthos, "Eternal Rage's Ethos", Id, Offensive, 15),
+        exclusive(
+            EternalRagesMettle,
+            "Eternal Rage's Mettle",
+            Id,
+            Offensive,
+            15,
+        ),
+        info(FastLearner, "Fast Learner", &[], Utility, 15),
+        exclusive(FearlessDrive, "Fearless Drive", Ghandagoza, Offensive, 15),
+        exclusive(FearlessSpirit, "Fearless Spirit", Ghandagoza, Offensive, 15),
+        info(FirmStance, "Firm Stance", &[], Defensive, 15),
+        info(FlightOverFight, "Flight Over Fight", &[], Offensive, 15),
+        exclusive(
+            FoundersStrategy,
+            "Founder's Strategy",
+            Cagliostro,
+            Offensive,
+            15,
+        ),
+        exclusive(FoundersTruth, "Founder's Truth", Cagliostro, Offensive, 15),
+        info(Gamma, "Gamma", &[], Offensive, 45),
+        info(Garrison, "Garrison", &[], Defensive, 15),
+        info(
+            GlaciateResistance,
+            "Glaciate Resistance",
+            &[],
+            Resistance,
+            15,
+        ),
+        info(GlassCannon, "Glass Cannon", &[], Offensive, 15),
+        exclusive(
+            GuardiansConviction,
+            "Guardian's Conviction",
+            Vane,
+            Defensive,
+            15,
+        ),
+        exclusive(GuardiansHonor, "Guardian's Honor", Vane, Defensive, 15),
+        info(GuardPayback, "Guard Payback", &[], Offensive, 15),
+        info(Guts, "Guts", &[], Defensive, 15),
+        info(
+            HeldUnderResistance,
+            "Held Under Resistance",
+            &[],
+            Resistance,
+            15,
+        ),
+        exclusive(
+            HelmsmansNavigation,
+            "Helmsman's Navigation",
+            Rackam,
+            Offensive,
+            15,
+        ),
+        exclusive(
+            HelmsmansTenacity,
+            "Helmsman's Tenacity",
+            Rackam,
+            Offensive,
+            15,
+        ),
+        exclusive(HerosCreed, "Hero's Creed", Captain, Offensive, 15),
+        exclusive(HerosWill, "Hero's Will", Captain, Offensive, 15),
+        exclusive(
+            HolyKnightsGrandeur,
+            "Holy Knight's Grandeur",
+            Charlotta,
+            Offensive,
+            15,
+        ),
+        exclusive(
+            HolyKnightsLuster,
+            "Holy Knight's Luster",
+            Charlotta,
+            Offensive,
+            15,
+        ),
+        info(HP, "HP", &[], Defensive, 65),
+        info(ImprovedDodge, "Improved Dodge", &[], Defensive, 30),
+        info(ImprovedGuard, "Improved Guard", &[], Defensive, 30),
+        info(ImprovedHealing, "Improved Healing", &[], Defensive, 15),
+        info(InjuryToInsult, "Injury To Insult", &[], Offensive, 15),
+        info(LessIsMore, "Less Is More", &[], Offensive, 15),
+        info(LifeOnTheLine, "Life On The Line", &[], Offensive, 15),
+        info(LinkedTogether, "Linked Together", &[], Utility, 15),
+        exclusive(LordsAmbition, "Lord's Ambition", Percival, Offensive, 15),
+        exclusive(
+            LordsProcession,
+            "Lord's Procession",
+            Percival,
+            Offensive,
+            15,
+        ),
+        info(LowProfile, "Low Profile", &[], Utility, 15),
+        info(LuckyCharge, "Lucky Charge", &[], Offensive, 15),
+        exclusive(MagesAspiration, "Mage's Aspiration", Io, Offensive, 15),
+        exclusive(MagesSavvy, "Mage's Savvy", Io, Offensive, 15),
+        info(NaturalDefenses, "Natural Defenses", &[], Defensive, 15),
+        info(NimbleDefense, "Nimble Defense", &[], Defensive, 15),
+        info(NimbleOnslaught, "Nimble Onslaught", &[], Offensive, 15),
+        info(OverdriveAssassin, "Overdrive Assassin", &[], Offensive, 15),
+        info(
+            ParalysisResistance,
+            "Paralysis Resistance",
+            &[],
+            Resistance,
+            15,
+        ),
+        info(PathToMastery, "Path To Mastery", &[], Utility, 15),
+        exclusive(PhantasmsConcord, "Phantasm's Concord", Ferry, Offensive, 15),
+        exclusive(PhantasmsHarmony, "Phantasm's Harmony", Ferry, Offensive, 15),
+        info(PoisonResistance, "Poison Resistance", &[], Resistance, 15),
+        info(PotentGreens, "Potent Greens", &[], Utility, 15),
+        info(PotionHoarder, "Potion Hoarder", &[], Utility, 15),
+        info(PowerHungry, "Power Hungry", &[], Offensive, 15),
+        exclusive(
+            PreciseResilience,
+            "Precise Resilience",
+            Tweyen,
+            Defensive,
+            15,
+        ),
+        exclusive(PreciseWrath, "Precise Wrath", Tweyen, Offensive, 15),
+        info(Provoke, "Provoke", &[], Utility, 15),
+        info(QuickCharge, "Quick Charge", &[], Utility, 15),
+        info(QuickCooldown, "Quick Cooldown", &[], Utility, 15),
+        info(Regen, "Regen", &[], Defensive, 15),
+        info(RollOfTheDie, "Roll Of The Die", &[], Offensive, 15),
+        exclusive(RosesBlooming, "Rose's Blooming", Rosetta, Offensive, 15),
+        exclusive(RosesProfusion, "Rose's Profusion", Rosetta, Offensive, 15),
+        info(RupieTycoon, "Rupie Tycoon", &[], Utility, 15),
+        info(
+            SandtombResistance,
+            "Sandtomb Resistance",
+            &[],
+            Resistance,
+            15,
+        ),
+        info(
+            SBASealedResistance,
+            "SBA Sealed Resistance",
+            &[],
+            Resistance,
+            15,
+        ),
+        info(SigilBooster, "Sigil Booster", &[], Utility, 15),
+        info(SkilledAssault, "Skilled Assault", &[], Offensive, 30),
+        info(
+            SkillSealedResistance,
+            "Skill Sealed Resistance",
+            &[],
+            Resistance,
+            15,
+        ),
+        info(SlowResistance, "Slow Resistance", &[], Resistance, 15),
+        info(Stamina, "Stamina", &[], Offensive, 45),
+        info(SteadyFocus, "Steady Focus", &[], Utility, 15),
+        info(SteelNerves, "Steel Nerves", &[], Defensive, 15),
+        info(StoutHeart, "Stout Heart", &[], Defensive, 15),
+        info(StunPower, "Stun Power", &[], Offensive, 65),
+        info(
+            SupplementaryDamage,
+            "Supplementary DMG",
+            &["Supplementary Damage", "Supp DMG", "Supp Damage"],
+            Offensive,
+            65,
+        ),
+        exclusive(SwordmastersArt, "Swordmaster's Art", Yodarha, Offensive, 15),
+        exclusive(
+            SwordmastersProwess,
+            "Swordmaster's Prowess",
+            Yodarha,
+            Offensive,
+            15,
+        ),
+        info(ThrowDMG, "Throw DMG", &[], Offensive, 30),
+        info(Tyranny, "Tyranny", &[], Offensive, 45),
+        info(Uplift, "Uplift", &[], Utility, 15),
+        exclusive(
+            VersalisFoundation,
+            "Versalis Foundation",
+            Seofon,
+            Offensive,
+            15,
+        ),
+        exclusive(VersalisIgnition, "Versalis Ignition", Seofon, Offensive, 15),
+        exclusive(VeteransInsight, "Veteran's Insight", Eugen, Offensive, 15),
+        exclusive(VeteransVision, "Veteran's Vision", Eugen, Offensive, 15),
+        info(WarElemental, "War Elemental", &[], Offensive, 15),
+        info(
+            WeakPointDMG,
+            "Weak Point DMG",
+            &["Weak Point Damage"],
+            Offensive,
+            30,
+        ),
+        exclusive(
+            WhiteDragonsGlory,
+            "White Dragon's Glory",
+            Lancelot,
+            Offensive,
+            15,
+        ),
+        exclusive(
+            WhiteDragonsOath,
+            "White Dragon's Oath",
+            Lancelot,
+            Offensive,
+            15,
+        ),
+    ]
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_matches_discriminants() {
+        for (i, info) in TRAIT_INFO.iter().enumerate() {
+            assert_eq!(info.kind as usize, i, "{:?} is out of order", info.kind);
+        }
+    }
+
+    #[test]
+    fn exclusive_traits() {
+        assert_eq!(TraitKind::HerosCreed.character(), Some(Character::Captain));
+        assert!(TraitKind::SwordmastersArt.is_usable_by(Character::Yodarha));
+        assert!(!TraitKind::SwordmastersArt.is_usable_by(Character::Captain));
+        assert!(TraitKind::DMGCap.is_usable_by(Character::Captain));
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for kind in TraitKind::all() {
+            assert_eq!(kind.to_string().parse::<TraitKind>(), Ok(kind));
+        }
+    }
+}
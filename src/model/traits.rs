@@ -1,18 +1,23 @@
 use std::collections::hash_map::Iter as HashMapIter;
 use std::{collections::HashMap, fmt::Display, str::FromStr};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct TraitSet {
+    /// serialized in a fixed order, so that saved files do not change between runs
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_sorted"))]
     traits: HashMap<TraitKind, u16>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Trait {
     pub kind: TraitKind,
     pub level: u8,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum TraitKind {
     Aegis,
     Alpha,
@@ -151,9 +156,7 @@ impl Trait {
 
 impl TraitSet {
     pub fn new() -> Self {
-        TraitSet {
-            traits: HashMap::new(),
-        }
+        TraitSet::default()
     }
 
     pub fn add(&mut self, t: Trait) {
@@ -163,17 +166,53 @@ impl TraitSet {
             .or_insert(t.level as u16);
     }
 
+    /// takes away the levels of a trait that was added before
+    pub fn remove(&mut self, t: Trait) {
+        if let Some(level) = self.traits.get_mut(&t.kind) {
+            *level = level.saturating_sub(t.level as u16);
+            if *level == 0 {
+                self.traits.remove(&t.kind);
+            }
+        }
+    }
+
+    /// sets the level of `kind`, replacing the level it had
+    pub fn insert(&mut self, kind: TraitKind, level: u16) {
+        self.traits.insert(kind, level);
+    }
+
     pub fn level(&self, kind: TraitKind) -> u16 {
         self.traits.get(&kind).copied().unwrap_or(0)
     }
 
+    /// the level of `kind` that has an effect in game, limited to its max level
+    pub fn effective_level(&self, kind: TraitKind) -> u16 {
+        self.level(kind).min(kind.max_level())
+    }
+
+    /// the levels of `kind` above its max level, which have no effect
+    pub fn overflow(&self, kind: TraitKind) -> u16 {
+        self.level(kind).saturating_sub(kind.max_level())
+    }
+
+    /// the levels above the max level summed over all traits
+    pub fn wasted_levels(&self) -> u16 {
+        self.traits.keys().map(|&kind| self.overflow(kind)).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.traits.is_empty()
+    }
+
     pub fn contains(&self, kind: TraitKind) -> bool {
         self.traits.contains_key(&kind)
     }
 
+    /// compares effective levels, so levels above a trait's max level never count as better
     pub fn is_superset_of(&self, other: &TraitSet) -> bool {
-        other.iter().all(|(desired_k, desired_l)| {
-            self.traits.get(desired_k).is_some_and(|l| l >= desired_l)
+        other.iter().all(|(&desired_k, _)| {
+            self.contains(desired_k)
+                && self.effective_level(desired_k) >= other.effective_level(desired_k)
         })
     }
 
@@ -200,7 +239,13 @@ impl FromIterator<Trait> for TraitSet {
 
 impl Display for Trait {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?} {}", self.kind, self.level)
+        write!(f, "{} {}", self.kind, self.level)
+    }
+}
+
+impl Display for TraitKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
     }
 }
 
@@ -208,127 +253,84 @@ impl FromStr for TraitKind {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use TraitKind::*;
-        match s {
-            "Aegis" => Ok(Aegis),
-            "Alpha" => Ok(Alpha),
-            "ATK Down Resistance" => Ok(ATKDownResistance),
-            "ATK" => Ok(ATK),
-            "Autorevive" => Ok(Autorevive),
-            "Berserker" => Ok(Berserker),
-            "Beta" => Ok(Beta),
-            "Blight Resistance" => Ok(BlightResistance),
-            "Break Assassin" => Ok(BreakAssassin),
-            "Burn Resistance" => Ok(BurnResistance),
-            "Butterfly's Grace" => Ok(ButterflysGrace),
-            "Butterfly's Valor" => Ok(ButterflysValor),
-            "Cascade" => Ok(Cascade),
-            "Catastrophe" => Ok(Catastrophe),
-            "Charged Attack DMG" => Ok(ChargedAttackDMG),
-            "Combo Booster" => Ok(ComboBooster),
-            "Combo Finisher DMG" => Ok(ComboFinisherDMG),
-            "Concentrated Fire" => Ok(ConcentratedFire),
-            "Crabvestment Returns" => Ok(CrabvestmentReturns),
-            "Crabby Resonance" => Ok(CrabbyResonance),
-            "Crimson's Clout" => Ok(CrimsonsClout),
-            "Crimson's Flight" => Ok(CrimsonsFlight),
-            "Critical Hit DMG" => Ok(CriticalHitDMG),
-            "Critical Hit Rate" => Ok(CriticalHitRate),
-            "Darkflame Resistance" => Ok(DarkflameResistance),
-            "DEF Down Resistance" => Ok(DEFDownDesistance),
-            "Dizzy Resistance" => Ok(DizzyResistance),
-            "DMG Cap" => Ok(DMGCap),
-            "Dodge Payback" => Ok(DodgePayback),
-            "Dragonslayer's Dominance" => Ok(DragonslayersDominance),
-            "Dragonslayer's Ingenuity" => Ok(DragonslayersIngenuity),
-            "Drain" => Ok(Drain),
-            "Ebony's Poise" => Ok(EbonysPoise),
-            "Ebony's Presence" => Ok(EbonysPresence),
-            "Enmity" => Ok(Enmity),
-            "Eternal Rage's Ethos" => Ok(EternalRagesEthos),
-            "Eternal Rage's Mettle" => Ok(EternalRagesMettle),
-            "Fast Learner" => Ok(FastLearner),
-            "Fearless Drive" => Ok(FearlessDrive),
-            "Fearless Spirit" => Ok(FearlessSpirit),
-            "Firm Stance" => Ok(FirmStance),
-            "Flight Over Fight" => Ok(FlightOverFight),
-            "Founder's Strategy" => Ok(FoundersStrategy),
-            "Founder's Truth" => Ok(FoundersTruth),
-            "Gamma" => Ok(Gamma),
-            "Garrison" => Ok(Garrison),
-            "Glaciate Resistance" => Ok(GlaciateResistance),
-            "Glass Cannon" => Ok(GlassCannon),
-            "Guardian's Conviction" => Ok(GuardiansConviction),
-            "Guardian's Honor" => Ok(GuardiansHonor),
-            "Guard Payback" => Ok(GuardPayback),
-            "Guts" => Ok(Guts),
-            "Held Under Resistance" => Ok(HeldUnderResistance),
-            "Helmsman's Navigation" => Ok(HelmsmansNavigation),
-            "Helmsman's Tenacity" => Ok(HelmsmansTenacity),
-            "Hero's Creed" => Ok(HerosCreed),
-            "Hero's Will" => Ok(HerosWill),
-            "Holy Knight's Grandeur" => Ok(HolyKnightsGrandeur),
-            "Holy Knight's Luster" => Ok(HolyKnightsLuster),
-            "HP" => Ok(HP),
-            "Improved Dodge" => Ok(ImprovedDodge),
-            "Improved Guard" => Ok(ImprovedGuard),
-            "Improved Healing" => Ok(ImprovedHealing),
-            "Injury To Insult" => Ok(InjuryToInsult),
-            "Less Is More" => Ok(LessIsMore),
-            "Life On The Line" => Ok(LifeOnTheLine),
-            "LinkedTogether" => Ok(LinkedTogether),
-            "Lord's Ambition" => Ok(LordsAmbition),
-            "Lord's Procession" => Ok(LordsProcession),
-            "Low Profile" => Ok(LowProfile),
-            "Lucky Charge" => Ok(LuckyCharge),
-            "Mage's Aspiration" => Ok(MagesAspiration),
-            "Mage's Savvy" => Ok(MagesSavvy),
-            "Natural Defenses" => Ok(NaturalDefenses),
-            "Nimble Defense" => Ok(NimbleDefense),
-            "Nimble Onslaught" => Ok(NimbleOnslaught),
-            "Overdrive Assassin" => Ok(OverdriveAssassin),
-            "Paralysis Resistance" => Ok(ParalysisResistance),
-            "Path To Mastery" => Ok(PathToMastery),
-            "Phantasm's Concord" => Ok(PhantasmsConcord),
-            "Phantasm's Harmony" => Ok(PhantasmsHarmony),
-            "Potion Hoarder" => Ok(PotionHoarder),
-            "Power Hungry" => Ok(PowerHungry),
-            "Precise Resilience" => Ok(PreciseResilience),
-            "Precise Wrath" => Ok(PreciseWrath),
-            "Provoke" => Ok(Provoke),
-            "Quick Charge" => Ok(QuickCharge),
-            "Quick Cooldown" => Ok(QuickCooldown),
-            "Regen" => Ok(Regen),
-            "Roll Of The Die" => Ok(RollOfTheDie),
-            "Rose's Blooming" => Ok(RosesBlooming),
-            "Rose's Profusion" => Ok(RosesProfusion),
-            "Rupie Tycoon" => Ok(RupieTycoon),
-            "Sandtomb Resistance" => Ok(SandtombResistance),
-            "SBA Sealed Resistance" => Ok(SBASealedResistance),
-            "Sigil Booster" => Ok(SigilBooster),
-            "Skilled Assault" => Ok(SkilledAssault),
-            "Skill Sealed Resistance" => Ok(SkillSealedResistance),
-            "Slow Resistance" => Ok(SlowResistance),
-            "Stamina" => Ok(Stamina),
-            "Steady Focus" => Ok(SteadyFocus),
-            "Steel Nerves" => Ok(SteelNerves),
-            "Stout Heart" => Ok(StoutHeart),
-            "Stun Power" => Ok(StunPower),
-            "Supplementary DMG" => Ok(SupplementaryDamage),
-            "Swordmaster's Art" => Ok(SwordmastersArt),
-            "Swordmaster's Prowess" => Ok(SwordmastersProwess),
-            "Throw DMG" => Ok(ThrowDMG),
-            "Tyranny" => Ok(Tyranny),
-            "Uplift" => Ok(Uplift),
-            "Versalis Foundation" => Ok(VersalisFoundation),
-            "Versalis Ignition" => Ok(VersalisIgnition),
-            "Veteran's Insight" => Ok(VeteransInsight),
-            "Veteran's Vision" => Ok(VeteransVision),
-            "War Elemental" => Ok(WarElemental),
-            "Weak Point DMG" => Ok(WeakPointDMG),
-            "White Dragon's Glory" => Ok(WhiteDragonsGlory),
-            "White Dragon's Oath" => Ok(WhiteDragonsOath),
-            _ => Err("invalid trait name"),
-        }
+        TraitKind::from_name(s).ok_or("invalid trait name")
+    }
+}
+
+/// trait kinds are stored as their name shown in game
+#[cfg(feature = "serde")]
+impl serde::Serialize for TraitKind {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TraitKind {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        TraitKind::from_name(&name)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid trait name `{name}`")))
+    }
+}
+
+#[cfg(feature = "serde")]
+fn serialize_sorted<S: serde::Serializer>(
+    traits: &HashMap<TraitKind, u16>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(traits.iter().collect::<std::collections::BTreeMap<_, _>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use TraitKind as TK;
+
+    use super::*;
+
+    #[test]
+    fn display_uses_game_name() {
+        assert_eq!(Trait::new(TK::DMGCap, 15).to_string(), "DMG Cap 15");
+        assert_eq!(TK::HerosCreed.to_string(), "Hero's Creed");
+    }
+
+    #[test]
+    fn levels_above_max_are_wasted() {
+        let traits: TraitSet = [
+            Trait::new(TK::DMGCap, 40),
+            Trait::new(TK::DMGCap, 40),
+            Trait::new(TK::Aegis, 10),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(traits.level(TK::DMGCap), 80);
+        assert_eq!(traits.effective_level(TK::DMGCap), 65);
+        assert_eq!(traits.overflow(TK::DMGCap), 15);
+        assert_eq!(traits.overflow(TK::Aegis), 0);
+        assert_eq!(traits.wasted_levels(), 15);
+    }
+
+    #[test]
+    fn superset_compares_effective_levels() {
+        let capped: TraitSet = [Trait::new(TK::DMGCap, 65)].into_iter().collect();
+        let over: TraitSet = [Trait::new(TK::DMGCap, 80)].into_iter().collect();
+
+        assert!(capped.is_superset_of(&over));
+        assert!(over.is_superset_of(&capped));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_game_names() {
+        let traits: TraitSet = [Trait::new(TK::Gamma, 15), Trait::new(TK::DMGCap, 30)]
+            .into_iter()
+            .collect();
+
+        let json = serde_json::to_string(&traits).unwrap();
+
+        assert_eq!(json, r#"{"DMG Cap":30,"Gamma":15}"#);
+        assert_eq!(serde_json::from_str::<TraitSet>(&json).unwrap(), traits);
+        assert!(serde_json::from_str::<TraitKind>(r#""Gama""#).is_err());
     }
 }
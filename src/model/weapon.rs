@@ -0,0 +1,46 @@
+use std::fmt::Display;
+
+use super::Trait;
+
+/// a weapon and the traits it grants on its own.
+/// the traits of the wrightstone imbued into it are part of the build's wrightstone
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Weapon {
+    pub name: String,
+    pub trait1: Trait,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub trait2: Option<Trait>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub trait3: Option<Trait>,
+}
+
+impl Weapon {
+    pub fn traits(&self) -> impl Iterator<Item = Trait> {
+        [Some(self.trait1), self.trait2, self.trait3]
+            .into_iter()
+            .flatten()
+    }
+}
+
+impl Display for Weapon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}", self.name, self.trait1)?;
+
+        if let Some(t2) = self.trait2 {
+            write!(f, " + {t2}")?;
+        }
+
+        if let Some(t3) = self.trait3 {
+            write!(f, " + {t3}")?;
+        }
+
+        write!(f, "]")
+    }
+}
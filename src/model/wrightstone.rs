@@ -2,13 +2,39 @@ use std::fmt::Display;
 
 use super::Trait;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Wrightstone {
     pub trait1: Trait,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub trait2: Option<Trait>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
     pub trait3: Option<Trait>,
 }
 
+impl Wrightstone {
+    pub fn traits(&self) -> impl Iterator<Item = Trait> {
+        [Some(self.trait1), self.trait2, self.trait3]
+            .into_iter()
+            .flatten()
+    }
+
+    /// the same wrightstone with its second and third trait in a fixed order,
+    /// so that wrightstones with the same traits compare as equal
+    pub fn canonical(mut self) -> Self {
+        if self.trait2.is_none() || (self.trait3.is_some() && self.trait3 < self.trait2) {
+            std::mem::swap(&mut self.trait2, &mut self.trait3);
+        }
+        self
+    }
+}
+
 impl Display for Wrightstone {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "[{}", self.trait1)?;
@@ -24,3 +50,25 @@ impl Display for Wrightstone {
         write!(f, "]")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::model::TraitKind as TK;
+
+    use super::*;
+
+    #[test]
+    fn canonical_orders_second_and_third_trait() {
+        let stone = |t2, t3| Wrightstone {
+            trait1: Trait::new(TK::DMGCap, 20),
+            trait2: t2,
+            trait3: t3,
+        };
+        let hp = Some(Trait::new(TK::HP, 10));
+        let drain = Some(Trait::new(TK::Drain, 5));
+
+        assert_eq!(stone(hp, drain).canonical(), stone(drain, hp).canonical());
+        assert_eq!(stone(None, hp).canonical(), stone(hp, None));
+        assert_eq!(stone(hp, None).canonical(), stone(hp, None));
+    }
+}
@@ -0,0 +1,510 @@
+//! summaries of builds that show where every trait level comes from
+
+use std::fmt::Write;
+
+use crate::model::{SearchQuery, SearchResult, TraitKind};
+
+/// an item of a build that grants trait levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Source {
+    /// the sigil at this index of the build's sigils, starting at 0
+    Sigil(usize),
+    Wrightstone,
+    Weapon,
+}
+
+/// the level of one trait in a build compared to the level the query asks for
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TraitReport {
+    pub kind: TraitKind,
+    /// the desired level up to the trait's max level, `None` for bonus traits the query
+    /// did not ask for
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub required: Option<u16>,
+    pub achieved: u16,
+    /// the effective achieved level minus the desired level, negative when the build
+    /// falls short
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub surplus: Option<i32>,
+    /// the achieved levels above the trait's max level, which have no effect
+    pub wasted: u16,
+    pub sources: Vec<Source>,
+}
+
+/// a build with a breakdown of its traits, desired traits first and bonus traits after them
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct BuildReport {
+    pub build: SearchResult,
+    pub traits: Vec<TraitReport>,
+    /// the items of the build that the query locked
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub locked: Vec<Source>,
+    /// the sigil slots the build leaves empty for the player's own choice
+    pub free_slots: usize,
+}
+
+impl TraitReport {
+    pub fn is_bonus(&self) -> bool {
+        self.required.is_none()
+    }
+}
+
+impl BuildReport {
+    pub fn new(build: SearchResult, query: &SearchQuery) -> Self {
+        let achieved = build.traits();
+        let mut desired: Vec<TraitKind> = query.desired_traits.iter().map(|(&k, _)| k).collect();
+        desired.sort_unstable();
+        let mut bonus: Vec<TraitKind> = achieved
+            .iter()
+            .map(|(&k, _)| k)
+            .filter(|&k| !query.desired_traits.contains(k))
+            .collect();
+        bonus.sort_unstable();
+
+        let traits = desired
+            .into_iter()
+            .chain(bonus)
+            .map(|kind| {
+                let required = query
+                    .desired_traits
+                    .contains(kind)
+                    .then(|| query.desired_traits.effective_level(kind));
+                TraitReport {
+                    kind,
+                    required,
+                    achieved: achieved.level(kind),
+                    surplus: required
+                        .map(|r| i32::from(achieved.effective_level(kind)) - i32::from(r)),
+                    wasted: achieved.overflow(kind),
+                    sources: sources(&build, kind),
+                }
+            })
+            .collect();
+        let locked = locked(&build, query);
+        let free_slots = (query.sigil_slots as usize).saturating_sub(build.sigils.len());
+
+        BuildReport {
+            build,
+            traits,
+            locked,
+            free_slots,
+        }
+    }
+
+    pub fn desired_traits(&self) -> impl Iterator<Item = &TraitReport> {
+        self.traits.iter().filter(|t| !t.is_bonus())
+    }
+
+    pub fn bonus_traits(&self) -> impl Iterator<Item = &TraitReport> {
+        self.traits.iter().filter(|t| t.is_bonus())
+    }
+
+    pub fn is_locked(&self, source: Source) -> bool {
+        self.locked.contains(&source)
+    }
+}
+
+/// the build's copies of the locked sigils and its locked wrightstone
+fn locked(build: &SearchResult, query: &SearchQuery) -> Vec<Source> {
+    let mut locked = Vec::new();
+    for sigil in &query.locked_sigils {
+        let copy = (0..build.sigils.len())
+            .find(|&i| build.sigils[i] == *sigil && !locked.contains(&Source::Sigil(i)));
+        if let Some(i) = copy {
+            locked.push(Source::Sigil(i));
+        }
+    }
+    locked.sort_unstable();
+
+    if query.locked_wrightstone.is_some() && build.wrightstone == query.locked_wrightstone {
+        locked.push(Source::Wrightstone);
+    }
+
+    locked
+}
+
+fn sources(build: &SearchResult, kind: TraitKind) -> Vec<Source> {
+    let sigils = build
+        .sigils
+        .iter()
+        .enumerate()
+        .filter(|(_, sigil)| sigil.traits().any(|t| t.kind == kind))
+        .map(|(i, _)| Source::Sigil(i));
+    let wrightstone = build
+        .wrightstone
+        .as_ref()
+        .filter(|stone| stone.traits().any(|t| t.kind == kind))
+        .map(|_| Source::Wrightstone);
+    let weapon = build
+        .weapon
+        .as_ref()
+        .filter(|weapon| weapon.traits().any(|t| t.kind == kind))
+        .map(|_| Source::Weapon);
+
+    sigils.chain(wrightstone).chain(weapon).collect()
+}
+
+/// the builds as aligned plain text tables
+pub fn text(reports: &[BuildReport]) -> String {
+    reports
+        .iter()
+        .enumerate()
+        .map(|(i, report)| text_build(i + 1, report))
+        .collect()
+}
+
+/// one build as aligned plain text tables, headed by its number counting from 1
+pub fn text_build(number: usize, report: &BuildReport) -> String {
+    let mut out = String::new();
+
+    let build = &report.build;
+    writeln!(out, "Build {number}\n").unwrap();
+    writeln!(out, "Sigils:").unwrap();
+    for (n, sigil) in build.sigils.iter().enumerate() {
+        let lock = lock_note(report.is_locked(Source::Sigil(n)));
+        writeln!(out, "  {:>2}. {sigil}{lock}", n + 1).unwrap();
+    }
+    if report.free_slots > 0 {
+        writeln!(out, "Free sigil slots: {}", report.free_slots).unwrap();
+    }
+    if let Some(stone) = &build.wrightstone {
+        let lock = lock_note(report.is_locked(Source::Wrightstone));
+        writeln!(out, "Wrightstone: {stone}{lock}").unwrap();
+    }
+    if let Some(weapon) = &build.weapon {
+        writeln!(out, "Weapon: {weapon}").unwrap();
+    }
+    out.push('\n');
+
+    let width = report
+        .traits
+        .iter()
+        .map(|t| t.kind.name().chars().count())
+        .chain(["Bonus trait".len()])
+        .max()
+        .unwrap_or_default();
+
+    writeln!(
+        out,
+        "{:width$}  Required  Achieved  Surplus  Wasted  From",
+        "Trait"
+    )
+    .unwrap();
+    for t in report.desired_traits() {
+        writeln!(
+            out,
+            "{:width$}  {:>8}  {:>8}  {:>7}  {:>6}  {}",
+            t.kind.name(),
+            t.required.unwrap_or_default(),
+            t.achieved,
+            t.surplus.unwrap_or_default(),
+            t.wasted,
+            source_list(&t.sources)
+        )
+        .unwrap();
+    }
+
+    if report.bonus_traits().next().is_some() {
+        writeln!(out, "\n{:width$}  Achieved  Wasted  From", "Bonus trait").unwrap();
+        for t in report.bonus_traits() {
+            writeln!(
+                out,
+                "{:width$}  {:>8}  {:>6}  {}",
+                t.kind.name(),
+                t.achieved,
+                t.wasted,
+                source_list(&t.sources)
+            )
+            .unwrap();
+        }
+    }
+    out.push('\n');
+
+    out
+}
+
+/// the builds as Markdown, with a section and trait tables per build
+pub fn markdown(reports: &[BuildReport]) -> String {
+    reports
+        .iter()
+        .enumerate()
+        .map(|(i, report)| markdown_build(i + 1, report))
+        .collect()
+}
+
+/// one build as a Markdown section with its trait tables, headed by its number counting from 1
+pub fn markdown_build(number: usize, report: &BuildReport) -> String {
+    let mut out = String::new();
+
+    let build = &report.build;
+    writeln!(out, "## Build {number}\n").unwrap();
+    for (n, sigil) in build.sigils.iter().enumerate() {
+        let lock = lock_note(report.is_locked(Source::Sigil(n)));
+        writeln!(out, "1. {}{lock}", escape_markdown(&sigil.to_string())).unwrap();
+    }
+    out.push('\n');
+    if report.free_slots > 0 {
+        writeln!(out, "**Free sigil slots:** {}  ", report.free_slots).unwrap();
+    }
+    if let Some(stone) = &build.wrightstone {
+        let lock = lock_note(report.is_locked(Source::Wrightstone));
+        writeln!(
+            out,
+            "**Wrightstone:** {}{lock}  ",
+            escape_markdown(&stone.to_string())
+        )
+        .unwrap();
+    }
+    if let Some(weapon) = &build.weapon {
+        writeln!(
+            out,
+            "**Weapon:** {}  ",
+            escape_markdown(&weapon.to_string())
+        )
+        .unwrap();
+    }
+    if report.free_slots > 0 || build.wrightstone.is_some() || build.weapon.is_some() {
+        out.push('\n');
+    }
+
+    writeln!(
+        out,
+        "| Trait | Required | Achieved | Surplus | Wasted | From |"
+    )
+    .unwrap();
+    writeln!(out, "| --- | ---: | ---: | ---: | ---: | --- |").unwrap();
+    for t in report.desired_traits() {
+        writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} |",
+            t.kind.name(),
+            t.required.unwrap_or_default(),
+            t.achieved,
+            t.surplus.unwrap_or_default(),
+            t.wasted,
+            source_list(&t.sources)
+        )
+        .unwrap();
+    }
+
+    if report.bonus_traits().next().is_some() {
+        writeln!(out, "\n| Bonus trait | Achieved | Wasted | From |").unwrap();
+        writeln!(out, "| --- | ---: | ---: | --- |").unwrap();
+        for t in report.bonus_traits() {
+            writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                t.kind.name(),
+                t.achieved,
+                t.wasted,
+                source_list(&t.sources)
+            )
+            .unwrap();
+        }
+    }
+    out.push('\n');
+
+    out
+}
+
+/// the builds as a JSON array
+#[cfg(feature = "serde")]
+pub fn json(reports: &[BuildReport]) -> String {
+    serde_json::to_string_pretty(reports).expect("reports serialize to JSON")
+}
+
+/// the sources of a trait for people to read, numbering sigils from 1 as in the reports
+fn source_list(sources: &[Source]) -> String {
+    let sigils: Vec<String> = sources
+        .iter()
+        .filter_map(|source| match source {
+            Source::Sigil(i) => Some((i + 1).to_string()),
+            _ => None,
+        })
+        .collect();
+
+    let mut parts = Vec::new();
+    match sigils.len() {
+        0 => {}
+        1 => parts.push(format!("sigil {}", sigils[0])),
+        _ => parts.push(format!("sigils {}", sigils.join(", "))),
+    }
+    if sources.contains(&Source::Wrightstone) {
+        parts.push("wrightstone".to_string());
+    }
+    if sources.contains(&Source::Weapon) {
+        parts.push("weapon".to_string());
+    }
+
+    parts.join(", ")
+}
+
+fn lock_note(locked: bool) -> &'static str {
+    if locked {
+        " (locked)"
+    } else {
+        ""
+    }
+}
+
+/// escapes the characters in item descriptions that Markdown would read as formatting
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '*' | '_' | '[' | ']' | '`' | '|' | '<' | '>' | '#'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Sigil, Trait, TraitKind as TK, TraitSet, Weapon};
+
+    use super::*;
+
+    fn example() -> BuildReport {
+        let build = SearchResult::new(
+            vec![
+                Sigil {
+                    trait1: Trait::new(TK::Gamma, 15),
+                    trait2: Some(Trait::new(TK::DMGCap, 15)),
+                },
+                Sigil::new_single(Trait::new(TK::DMGCap, 15)),
+                Sigil::new_single(Trait::new(TK::HerosCreed, 15)),
+            ],
+            None,
+            Some(Weapon {
+                name: "Ascalon".to_string(),
+                trait1: Trait::new(TK::DMGCap, 10),
+                trait2: None,
+                trait3: None,
+            }),
+        );
+        let query = SearchQuery {
+            desired_traits: TraitSet::from_iter([
+                Trait::new(TK::DMGCap, 30),
+                Trait::new(TK::Gamma, 20),
+            ]),
+            sigil_slots: 3,
+            ..Default::default()
+        };
+
+        BuildReport::new(build, &query)
+    }
+
+    #[test]
+    fn breaks_down_traits() {
+        let report = example();
+
+        let dmg_cap = &report.traits[0];
+        assert_eq!(dmg_cap.kind, TK::DMGCap);
+        assert_eq!((dmg_cap.required, dmg_cap.achieved), (Some(30), 40));
+        assert_eq!(dmg_cap.surplus, Some(10));
+        assert_eq!(dmg_cap.sources.len(), 3);
+        assert!(dmg_cap.sources.contains(&Source::Weapon));
+
+        let gamma = &report.traits[1];
+        assert_eq!(gamma.surplus, Some(-5));
+
+        let bonus: Vec<TK> = report.bonus_traits().map(|t| t.kind).collect();
+        assert_eq!(bonus, [TK::HerosCreed]);
+    }
+
+    #[test]
+    fn levels_above_max_level_are_wasted_not_surplus() {
+        let aegis = Sigil::new_single(Trait::new(TK::Aegis, 15));
+        let build = SearchResult::new(vec![aegis.clone(), aegis], None, None);
+        let query = SearchQuery {
+            desired_traits: [Trait::new(TK::Aegis, 15)].into_iter().collect(),
+            sigil_slots: 2,
+            ..Default::default()
+        };
+
+        let report = BuildReport::new(build, &query);
+
+        let aegis = &report.traits[0];
+        assert_eq!(
+            (aegis.achieved, aegis.surplus, aegis.wasted),
+            (30, Some(0), 15)
+        );
+    }
+
+    #[test]
+    fn text_table() {
+        let text = text(&[example()]);
+
+        assert!(text.starts_with("Build 1\n\nSigils:\n"));
+        assert!(text.contains("Weapon: Ascalon [DMG Cap 10]\n"));
+        assert!(text.contains("Gamma               20        15       -5       0  sigil "));
+        assert!(text.contains("\nBonus trait   Achieved  Wasted  From\n"));
+    }
+
+    #[test]
+    fn markdown_tables() {
+        let markdown = markdown(&[example()]);
+
+        assert!(markdown.starts_with("## Build 1\n\n1. \\["));
+        assert!(markdown.contains("| DMG Cap | 30 | 40 | 10 | 0 | sigils "));
+        assert!(markdown.contains(", weapon |\n"));
+        assert!(markdown.contains("| Bonus trait | Achieved | Wasted | From |"));
+    }
+
+    #[test]
+    fn marks_locked_items() {
+        let build = example().build;
+        let query = SearchQuery {
+            locked_sigils: vec![Sigil::new_single(Trait::new(TK::DMGCap, 15))],
+            ..Default::default()
+        };
+
+        let report = BuildReport::new(build, &query);
+
+        assert_eq!(report.locked, [Source::Sigil(0)]);
+        assert!(text(&[report]).contains(" 1. [DMG Cap 15] (locked)\n"));
+    }
+
+    #[test]
+    fn counts_free_slots() {
+        let mut report = example();
+        assert_eq!(report.free_slots, 0);
+        assert!(!text(&[report.clone()]).contains("Free sigil slots"));
+
+        let query = SearchQuery {
+            sigil_slots: 5,
+            ..Default::default()
+        };
+        report = BuildReport::new(report.build, &query);
+        assert_eq!(report.free_slots, 2);
+        assert!(text(&[report]).contains("\nFree sigil slots: 2\n"));
+    }
+
+    #[test]
+    fn source_lists() {
+        assert_eq!(source_list(&[Source::Sigil(0)]), "sigil 1");
+        assert_eq!(
+            source_list(&[Source::Sigil(0), Source::Sigil(2), Source::Wrightstone]),
+            "sigils 1, 3, wrightstone"
+        );
+        assert_eq!(source_list(&[Source::Weapon]), "weapon");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_report() {
+        let json = json(&[example()]);
+
+        assert!(json.contains(r#""kind": "Hero's Creed""#));
+        assert!(json.contains(r#""surplus": -5"#));
+        assert!(json.contains(r#""sigil": 0"#));
+    }
+}
@@ -0,0 +1,974 @@
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::ops::ControlFlow;
+
+use itertools::Itertools;
+
+use crate::model::{
+    SearchPool, SearchQuery, SearchResult, Sigil, Stack, Trait, TraitKind, Weapon, Wrightstone,
+};
+
+use super::objective::TopK;
+use super::util::{can_lock, choices, filter_pool, is_desired_trait, is_minimal, is_valid_result};
+use super::{Objective, SmallestBuilds, Solver};
+
+/// an exact solver that explores sigil choices depth first and prunes every branch
+/// that can no longer reach the desired trait levels.
+/// for every stack of identical sigils it decides how many copies to use, so equivalent
+/// builds are only explored once.
+///
+/// returns the same set of results as `NaiveSolver`, possibly in a different order,
+/// without enumerating every combination.
+pub struct BranchBoundSolver;
+
+impl Solver for BranchBoundSolver {
+    fn is_exact(&self) -> bool {
+        true
+    }
+
+    fn search_each(
+        &self,
+        pool: SearchPool,
+        query: &SearchQuery,
+        sink: &mut dyn FnMut(SearchResult) -> ControlFlow<()>,
+    ) {
+        if !can_lock(&pool, query) {
+            return;
+        }
+
+        let pool = sorted_pool(pool, query);
+        let search = Search::new(&pool, query);
+        let _ = search.start(sink);
+    }
+
+    /// searches by increasing number of sigils like the default, but filters and indexes
+    /// the pool only once and, once `k` builds of a size are found, prunes the branches
+    /// that cannot beat their total surplus
+    fn search_fewest_sigils(
+        &self,
+        pool: SearchPool,
+        query: &SearchQuery,
+        k: usize,
+    ) -> SmallestBuilds {
+        let mut results = Vec::new();
+
+        if k > 0 && can_lock(&pool, query) {
+            let query = SearchQuery {
+                allow_empty_slots: false,
+                ..query.clone()
+            };
+            let pool = sorted_pool(pool, &query);
+            let mut search = Search::new(&pool, &query);
+
+            for num_sigils in 0..=search.num_sigils {
+                search.num_sigils = num_sigils;
+                search.surplus_floor.set(None);
+
+                let mut best = TopK::new(k);
+                let _ = search.start(&mut |result| {
+                    best.push(Objective::TotalSurplus.score(&result, &query), result);
+                    search.surplus_floor.set(best.worst_score());
+                    ControlFlow::Continue(())
+                });
+                results = best.into_sorted_vec();
+                if !results.is_empty() {
+                    break;
+                }
+            }
+        }
+
+        SmallestBuilds {
+            results,
+            proven: true,
+        }
+    }
+}
+
+/// the pool without the items no build can use, trying the strongest sigils first
+/// so the bounds shrink sooner
+fn sorted_pool(pool: SearchPool, query: &SearchQuery) -> SearchPool {
+    let mut pool = filter_pool(pool, query);
+    pool.sigils.sort_by_cached_key(|Stack { item: sigil, .. }| {
+        let desired_level: u16 = [Some(sigil.trait1), sigil.trait2]
+            .into_iter()
+            .flatten()
+            .filter(|t| is_desired_trait(*t, query))
+            .map(|t| t.level as u16)
+            .sum();
+        Reverse(desired_level)
+    });
+    pool
+}
+
+struct Search<'s> {
+    sigils: &'s [Stack<Sigil>],
+    wrightstones: &'s [Stack<Wrightstone>],
+    weapons: &'s [Weapon],
+    query: &'s SearchQuery,
+    /// the number of sigils to choose besides the locked sigils
+    num_sigils: usize,
+    /// the total surplus a build has to beat to be of any use, if any
+    surplus_floor: Cell<Option<i64>>,
+    locked_sigils: &'s [Sigil],
+    /// the level the locked sigils contribute to every desired trait
+    locked_totals: Vec<u16>,
+    /// the level the locked sigils contribute to every limited trait
+    locked_limited: Vec<u16>,
+    /// the desired level of every desired trait
+    desired: Vec<u16>,
+    /// the max level of every desired trait, above which levels have no effect
+    caps: Vec<u16>,
+    /// the level one sigil of each stack contributes to every desired trait
+    contributions: Vec<Vec<u16>>,
+    /// the number of sigils in all stacks from index `i` onwards
+    suffix_count: Vec<usize>,
+    /// the combined level of the `r` best sigils from stack `i` onwards for every desired trait,
+    /// indexed as `[i][d][r]`
+    suffix_best: Vec<Vec<Vec<u16>>>,
+    /// the combined level of the `r` best sigils from stack `i` onwards for all desired traits
+    /// together, indexed as `[i][r]`
+    suffix_best_total: Vec<Vec<u16>>,
+    /// the level every wrightstone and weapon pair contributes to every desired trait,
+    /// leaving out pairs that another pair matches or beats on every desired trait
+    gear: Vec<Vec<u16>>,
+    /// the max level of every trait the query limits
+    max_levels: Vec<u16>,
+    /// the level one sigil of each stack contributes to every limited trait
+    limit_contributions: Vec<Vec<u16>>,
+    /// the level of every optional trait above which it adds nothing to the optional score
+    optional_needed: Vec<u16>,
+    /// the level the locked sigils contribute to every optional trait
+    locked_optional: Vec<u16>,
+    /// the level one sigil of each stack contributes to every optional trait
+    optional_contributions: Vec<Vec<u16>>,
+}
+
+impl<'s> Search<'s> {
+    fn new(pool: &'s SearchPool, query: &'s SearchQuery) -> Self {
+        // levels above a trait's max level have no effect, so they are never required
+        let (kinds, desired): (Vec<TraitKind>, Vec<u16>) = query
+            .desired_traits
+            .iter()
+            .map(|(&kind, _)| (kind, query.desired_traits.effective_level(kind)))
+            .unzip();
+
+        let contributions: Vec<Vec<u16>> = pool
+            .sigils
+            .iter()
+            .map(|Stack { item: sigil, .. }| {
+                let traits = [Some(sigil.trait1), sigil.trait2];
+                contribution(&kinds, &traits)
+            })
+            .collect();
+
+        let (limited_kinds, max_levels): (Vec<TraitKind>, Vec<u16>) = query
+            .max_levels
+            .iter()
+            .map(|(&kind, &max)| (kind, max))
+            .unzip();
+        let limit_contributions = pool
+            .sigils
+            .iter()
+            .map(|Stack { item: sigil, .. }| {
+                contribution(&limited_kinds, &[Some(sigil.trait1), sigil.trait2])
+            })
+            .collect();
+
+        let locked_traits: Vec<Option<Trait>> = query
+            .locked_sigils
+            .iter()
+            .flat_map(|sigil| sigil.traits().map(Some))
+            .collect();
+        let locked_totals = contribution(&kinds, &locked_traits);
+        let locked_limited = contribution(&limited_kinds, &locked_traits);
+
+        let (optional_kinds, optional_needed): (Vec<TraitKind>, Vec<u16>) = query
+            .optional_traits
+            .iter()
+            .map(|t| (t.kind, t.level.min(t.kind.max_level())))
+            .unzip();
+        let optional_contributions = pool
+            .sigils
+            .iter()
+            .map(|Stack { item: sigil, .. }| {
+                contribution(&optional_kinds, &[Some(sigil.trait1), sigil.trait2])
+            })
+            .collect();
+        let locked_optional = contribution(&optional_kinds, &locked_traits);
+
+        let unlocked_slots = query.unlocked_slots().unwrap_or(0);
+        let num_sigils = std::cmp::min(unlocked_slots, pool.sigil_count());
+
+        let len = pool.sigils.len();
+        let mut suffix_count = vec![0; len + 1];
+        let mut suffix_best = vec![vec![vec![0; num_sigils + 1]; kinds.len()]; len + 1];
+        let mut suffix_best_total = vec![vec![0; num_sigils + 1]; len + 1];
+        for i in (0..len).rev() {
+            // more copies than slots can never be used
+            let copies = (pool.sigils[i].count as usize).min(num_sigils);
+            suffix_count[i] = suffix_count[i + 1] + pool.sigils[i].count as usize;
+
+            for d in 0..kinds.len() {
+                let mut sums = suffix_best[i + 1][d].clone();
+                for _ in 0..copies {
+                    sums = best_sums(&sums, contributions[i][d]);
+                }
+                suffix_best[i][d] = sums;
+            }
+
+            let total = contributions[i].iter().sum();
+            let mut sums = suffix_best_total[i + 1].clone();
+            for _ in 0..copies {
+                sums = best_sums(&sums, total);
+            }
+            suffix_best_total[i] = sums;
+        }
+
+        let stones = choices(pool.wrightstones.iter().map(|stack| &stack.item).collect());
+        let weapons = choices(pool.weapons.iter().collect());
+        let pairs: Vec<Vec<u16>> = stones
+            .iter()
+            .cartesian_product(&weapons)
+            .map(|(stone, weapon)| {
+                let stone_traits = stone.iter().flat_map(|stone| stone.traits());
+                let weapon_traits = weapon.iter().flat_map(|weapon| weapon.traits());
+                let traits: Vec<Option<Trait>> =
+                    stone_traits.chain(weapon_traits).map(Some).collect();
+                contribution(&kinds, &traits)
+            })
+            .unique()
+            .collect();
+        let gear = pairs
+            .iter()
+            .filter(|pair| {
+                !pairs.iter().any(|other| {
+                    other != *pair && other.iter().zip(pair.iter()).all(|(o, p)| o >= p)
+                })
+            })
+            .cloned()
+            .collect();
+
+        Search {
+            sigils: &pool.sigils,
+            wrightstones: &pool.wrightstones,
+            weapons: &pool.weapons,
+            query,
+            num_sigils,
+            surplus_floor: Cell::new(None),
+            locked_sigils: &query.locked_sigils,
+            locked_totals,
+            locked_limited,
+            desired,
+            caps: kinds.iter().map(|kind| kind.max_level()).collect(),
+            contributions,
+            suffix_count,
+            suffix_best,
+            suffix_best_total,
+            gear,
+            max_levels,
+            limit_contributions,
+            optional_needed,
+            locked_optional,
+            optional_contributions,
+        }
+    }
+
+    fn start(&self, sink: &mut dyn FnMut(SearchResult) -> ControlFlow<()>) -> ControlFlow<()> {
+        let mut chosen = Vec::with_capacity(self.num_sigils);
+        let mut totals = self.locked_totals.clone();
+        let mut limited = self.locked_limited.clone();
+        let mut optional = self.locked_optional.clone();
+        self.branch(
+            0,
+            &mut chosen,
+            &mut totals,
+            &mut limited,
+            &mut optional,
+            sink,
+        )
+    }
+
+    /// decides how many copies of the sigils in stack `stack` to use, trying more copies first
+    fn branch(
+        &self,
+        stack: usize,
+        chosen: &mut Vec<usize>,
+        totals: &mut [u16],
+        limited: &mut [u16],
+        optional: &mut [u16],
+        sink: &mut dyn FnMut(SearchResult) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let remaining = self.num_sigils - chosen.len();
+        let allow_empty = self.query.allow_empty_slots;
+        if remaining == 0 || (allow_empty && stack == self.sigils.len()) {
+            return self.complete(chosen, sink);
+        }
+
+        if allow_empty && self.has_redundant_sigil(chosen, totals, optional) {
+            // levels only grow as sigils are added, so the sigil stays redundant
+            return ControlFlow::Continue(());
+        }
+        if allow_empty && self.is_complete(totals, optional) {
+            // any further sigil could be left out again
+            return self.complete(chosen, sink);
+        }
+
+        let too_few_sigils = !allow_empty && self.suffix_count[stack] < remaining;
+        if too_few_sigils || !self.can_reach(stack, remaining, totals) {
+            return ControlFlow::Continue(());
+        }
+
+        let mut max_copies = (self.sigils[stack].count as usize).min(remaining);
+        if allow_empty {
+            max_copies = max_copies.min(self.useful_copies(stack, totals, optional));
+        }
+        for _ in 0..max_copies {
+            chosen.push(stack);
+            for (total, level) in totals.iter_mut().zip(&self.contributions[stack]) {
+                *total += level;
+            }
+            for (total, level) in limited.iter_mut().zip(&self.limit_contributions[stack]) {
+                *total += level;
+            }
+            for (total, level) in optional.iter_mut().zip(&self.optional_contributions[stack]) {
+                *total += level;
+            }
+        }
+
+        for copies in (0..=max_copies).rev() {
+            // levels only grow as sigils are added, so a limit once exceeded stays exceeded
+            let flow = if self.within_max_levels(limited) {
+                self.branch(stack + 1, chosen, totals, limited, optional, sink)
+            } else {
+                ControlFlow::Continue(())
+            };
+
+            if copies > 0 {
+                chosen.pop();
+                for (total, level) in totals.iter_mut().zip(&self.contributions[stack]) {
+                    *total -= level;
+                }
+                for (total, level) in limited.iter_mut().zip(&self.limit_contributions[stack]) {
+                    *total -= level;
+                }
+                for (total, level) in optional.iter_mut().zip(&self.optional_contributions[stack]) {
+                    *total -= level;
+                }
+            }
+
+            if flow.is_break() {
+                // undo the remaining copies before giving up
+                for _ in 1..copies {
+                    chosen.pop();
+                }
+                return flow;
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// whether a chosen sigil could be left out without missing a desired or optional level
+    /// the other sigils already reach, so no build in this branch is minimal
+    fn has_redundant_sigil(&self, chosen: &[usize], totals: &[u16], optional: &[u16]) -> bool {
+        let still_reached = |contributions: &[u16], levels: &[u16], needed: &[u16]| {
+            contributions
+                .iter()
+                .zip(levels)
+                .zip(needed)
+                .all(|((&level, &total), &needed)| level == 0 || total - level >= needed)
+        };
+
+        chosen.iter().dedup().any(|&stack| {
+            still_reached(&self.contributions[stack], totals, &self.desired)
+                && still_reached(
+                    &self.optional_contributions[stack],
+                    optional,
+                    &self.optional_needed,
+                )
+        })
+    }
+
+    /// whether the sigils chosen so far reach every desired and optional level on their own
+    fn is_complete(&self, totals: &[u16], optional: &[u16]) -> bool {
+        let reached = |levels: &[u16], needed: &[u16]| {
+            levels
+                .iter()
+                .zip(needed)
+                .all(|(level, needed)| level >= needed)
+        };
+        reached(totals, &self.desired) && reached(optional, &self.optional_needed)
+    }
+
+    /// how many copies of the sigils in stack `stack` can be added one after another while
+    /// each still adds to a desired or optional level that the sigils chosen before it miss.
+    /// a copy that adds nothing could be left out again, so no build with it is minimal
+    fn useful_copies(&self, stack: usize, totals: &[u16], optional: &[u16]) -> usize {
+        let adds_to = |contributions: &[u16], levels: &[u16], needed: &[u16], copies: u16| {
+            contributions
+                .iter()
+                .zip(levels)
+                .zip(needed)
+                .any(|((&level, &total), &needed)| level > 0 && total + copies * level < needed)
+        };
+
+        (0..self.sigils[stack].count)
+            .take_while(|&copies| {
+                adds_to(&self.contributions[stack], totals, &self.desired, copies)
+                    || adds_to(
+                        &self.optional_contributions[stack],
+                        optional,
+                        &self.optional_needed,
+                        copies,
+                    )
+            })
+            .count()
+    }
+
+    /// upper bound check: is there a wrightstone and weapon pair with which the remaining
+    /// slots could still make up the missing levels?
+    /// a trait can gain at most the levels of the best sigils left for it, and all traits
+    /// together at most the levels of the best sigils left counting only what each trait
+    /// still misses
+    fn can_reach(&self, start: usize, remaining: usize, totals: &[u16]) -> bool {
+        self.gear.iter().any(|gear| {
+            let deficits: Vec<u16> = totals
+                .iter()
+                .zip(gear)
+                .zip(&self.desired)
+                .map(|((&total, &gear), &desired)| desired.saturating_sub(total + gear))
+                .collect();
+            let total_deficit: u16 = deficits.iter().sum();
+
+            deficits
+                .iter()
+                .enumerate()
+                .all(|(d, &deficit)| deficit <= self.suffix_best[start][d][remaining])
+                && total_deficit <= self.suffix_best_total[start][remaining]
+                && self.can_cover(start, remaining, &deficits, total_deficit)
+                && self.can_beat_floor(start, remaining, totals, gear)
+        })
+    }
+
+    /// whether the builds in this branch could have more total surplus than the floor.
+    /// every trait gains at most the levels of the best sigils left for it,
+    /// and all traits together at most the levels of the best sigils left
+    fn can_beat_floor(&self, start: usize, remaining: usize, totals: &[u16], gear: &[u16]) -> bool {
+        let Some(floor) = self.surplus_floor.get() else {
+            return true;
+        };
+
+        let mut by_trait = 0;
+        let mut combined = self.suffix_best_total[start][remaining] as i64;
+        for d in 0..self.desired.len() {
+            let level = totals[d] + gear[d];
+            let best = (level + self.suffix_best[start][d][remaining]).min(self.caps[d]);
+            by_trait += best.saturating_sub(self.desired[d]) as i64;
+            combined += level.min(self.caps[d]) as i64 - self.desired[d] as i64;
+        }
+
+        by_trait.min(combined) > floor
+    }
+
+    /// whether `remaining` sigils from stack `start` onwards can add up to `total_deficit`
+    /// levels when each sigil only counts the levels of the traits still missing some
+    fn can_cover(
+        &self,
+        start: usize,
+        remaining: usize,
+        deficits: &[u16],
+        total_deficit: u16,
+    ) -> bool {
+        if total_deficit == 0 {
+            return true;
+        }
+
+        // the best capped levels found so far, highest first
+        let mut best: Vec<u16> = Vec::with_capacity(remaining);
+        let mut sum = 0;
+        for (stack, contributions) in self.sigils[start..]
+            .iter()
+            .zip(&self.contributions[start..])
+        {
+            let level: u16 = contributions
+                .iter()
+                .zip(deficits)
+                .map(|(&level, &deficit)| level.min(deficit))
+                .sum();
+            if level == 0 {
+                continue;
+            }
+
+            for _ in 0..(stack.count as usize).min(remaining) {
+                if best.len() == remaining {
+                    if best[remaining - 1] >= level {
+                        break;
+                    }
+                    sum -= best.pop().unwrap_or_default();
+                }
+                let at = best.partition_point(|&other| other >= level);
+                best.insert(at, level);
+                sum += level;
+            }
+
+            if sum >= total_deficit {
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn within_max_levels(&self, limited: &[u16]) -> bool {
+        limited
+            .iter()
+            .zip(&self.max_levels)
+            .all(|(level, max)| level <= max)
+    }
+
+    /// try every wrightstone and weapon with a full set of sigils,
+    /// or with any set of sigils if the query allows empty slots
+    fn complete(
+        &self,
+        chosen: &[usize],
+        sink: &mut dyn FnMut(SearchResult) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let chosen = chosen.iter().map(|&i| &self.sigils[i].item);
+        let sigils: Vec<Sigil> = self.locked_sigils.iter().chain(chosen).cloned().collect();
+
+        let wrightstones = choices(self.wrightstones.iter().map(|stack| &stack.item).collect());
+        let weapons = choices(self.weapons.iter().collect());
+
+        for (wrightstone, weapon) in wrightstones.iter().cartesian_product(&weapons) {
+            let candidate =
+                SearchResult::new(sigils.clone(), wrightstone.cloned(), weapon.cloned());
+            let minimal = !self.query.allow_empty_slots || is_minimal(&candidate, self.query);
+            if minimal && is_valid_result(&candidate, self.query) {
+                sink(candidate)?;
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+/// given the sums of the `r` highest levels seen so far for every `r`,
+/// returns the sums after also considering `level`
+fn best_sums(sums: &[u16], level: u16) -> Vec<u16> {
+    let mut new_sums = sums.to_vec();
+    for r in 1..sums.len() {
+        // the best `r` either skip `level`, or use it together with the best `r - 1`
+        new_sums[r] = sums[r].max(sums[r - 1] + level);
+    }
+    new_sums
+}
+
+/// the level `traits` contribute to each of `kinds`
+fn contribution(kinds: &[TraitKind], traits: &[Option<Trait>]) -> Vec<u16> {
+    kinds
+        .iter()
+        .map(|&kind| {
+            traits
+                .iter()
+                .flatten()
+                .filter(|t| t.kind == kind)
+                .map(|t| t.level as u16)
+                .sum()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use rand::prelude::*;
+    use rand::rngs::StdRng;
+
+    use crate::model::{Character, OptionalTrait, TraitKind as TK};
+    use crate::solver::testing::query;
+    use crate::solver::NaiveSolver;
+
+    use super::*;
+
+    fn pool() -> SearchPool {
+        let sigil = |t1: (TK, u8), t2: Option<(TK, u8)>| Sigil {
+            trait1: Trait::new(t1.0, t1.1),
+            trait2: t2.map(|(k, l)| Trait::new(k, l)),
+        };
+        let stone = |t1: (TK, u8), t2: (TK, u8)| Wrightstone {
+            trait1: Trait::new(t1.0, t1.1),
+            trait2: Some(Trait::new(t2.0, t2.1)),
+            trait3: None,
+        };
+
+        SearchPool::new(
+            vec![
+                sigil((TK::Gamma, 15), Some((TK::DMGCap, 15))),
+                sigil((TK::Gamma, 11), Some((TK::DMGCap, 11))),
+                sigil((TK::Gamma, 11), Some((TK::DMGCap, 11))),
+                sigil((TK::DMGCap, 15), Some((TK::ImprovedGuard, 15))),
+                sigil((TK::DMGCap, 15), Some((TK::ImprovedGuard, 15))),
+                sigil((TK::CriticalHitRate, 15), Some((TK::Drain, 15))),
+                sigil((TK::CriticalHitRate, 15), Some((TK::Autorevive, 15))),
+                sigil((TK::SupplementaryDamage, 15), None),
+                sigil((TK::Aegis, 15), Some((TK::PotionHoarder, 15))),
+            ],
+            vec![
+                stone((TK::CriticalHitRate, 10), (TK::HP, 6)),
+                stone((TK::CriticalHitRate, 7), (TK::Drain, 6)),
+                stone((TK::HP, 7), (TK::Autorevive, 5)),
+            ],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn matches_naive_solver() {
+        let query = query(
+            &[(TK::DMGCap, 45), (TK::Gamma, 22), (TK::CriticalHitRate, 20)],
+            5,
+        );
+
+        let naive = NaiveSolver.search(pool(), &query);
+        let branch_bound = BranchBoundSolver.search(pool(), &query);
+
+        assert!(!naive.is_empty());
+        assert_eq!(naive.len(), branch_bound.len());
+        for result in &naive {
+            assert!(branch_bound.contains(result));
+        }
+    }
+
+    #[test]
+    fn weapon_traits_count_toward_totals() {
+        let mut pool = pool();
+        pool.weapons.push(Weapon {
+            name: "Eternal Sword".to_string(),
+            trait1: Trait::new(TK::Gamma, 15),
+            trait2: None,
+            trait3: None,
+        });
+        let query = query(&[(TK::Gamma, 45)], 3);
+
+        let naive = NaiveSolver.search(pool.clone(), &query);
+        let branch_bound = BranchBoundSolver.search(pool, &query);
+
+        assert!(!branch_bound.is_empty());
+        assert_eq!(naive.len(), branch_bound.len());
+        assert!(branch_bound.iter().all(|r| r.weapon.is_some()));
+    }
+
+    #[test]
+    fn stops_after_limit() {
+        let query = query(&[(TK::DMGCap, 30)], 3);
+
+        let all = BranchBoundSolver.search(pool(), &query);
+        let first = BranchBoundSolver.search_first(pool(), &query, 2);
+
+        assert!(all.len() > 2);
+        assert_eq!(first, all[..2]);
+    }
+
+    #[test]
+    fn skips_other_characters_exclusive_sigils() {
+        let mut pool = pool();
+        let sigil = Sigil {
+            trait1: Trait::new(TK::DMGCap, 15),
+            trait2: Some(Trait::new(TK::SwordmastersArt, 15)),
+        };
+        pool.sigils.push(Stack::new(sigil, 1));
+        let query = |character| SearchQuery {
+            character,
+            ..query(&[(TK::DMGCap, 15)], 1)
+        };
+        let uses_exclusive = |results: Vec<SearchResult>| {
+            results
+                .iter()
+                .flat_map(|r| &r.sigils)
+                .any(|s| s.trait2.is_some_and(|t| t.kind.is_exclusive()))
+        };
+
+        let yodarha = BranchBoundSolver.search(pool.clone(), &query(Some(Character::Yodarha)));
+        let zeta = BranchBoundSolver.search(pool, &query(Some(Character::Zeta)));
+
+        assert!(uses_exclusive(yodarha));
+        assert!(!uses_exclusive(zeta));
+    }
+
+    #[test]
+    fn respects_max_levels() {
+        let mut query = query(&[(TK::DMGCap, 22)], 2);
+        query.max_levels.insert(TK::Gamma, 22);
+        query.max_levels.insert(TK::ImprovedGuard, 0);
+
+        let naive = NaiveSolver.search(pool(), &query);
+        let branch_bound = BranchBoundSolver.search(pool(), &query);
+
+        assert!(!branch_bound.is_empty());
+        assert_eq!(naive.len(), branch_bound.len());
+        for result in &branch_bound {
+            let traits = result.traits();
+            assert!(traits.level(TK::Gamma) <= 22);
+            assert!(!traits.contains(TK::ImprovedGuard));
+        }
+    }
+
+    #[test]
+    fn keeps_locked_items() {
+        let pool = pool();
+        let locked_sigil = Sigil::new_single(Trait::new(TK::SupplementaryDamage, 15));
+        let locked_stone = pool.wrightstones[2].item.clone();
+        let query = SearchQuery {
+            locked_sigils: vec![locked_sigil.clone()],
+            locked_wrightstone: Some(locked_stone.clone()),
+            ..query(&[(TK::DMGCap, 30)], 3)
+        };
+
+        let naive = NaiveSolver.search(pool.clone(), &query);
+        let branch_bound = BranchBoundSolver.search(pool, &query);
+
+        assert!(!branch_bound.is_empty());
+        assert_eq!(naive.len(), branch_bound.len());
+        for result in &branch_bound {
+            assert_eq!(result.sigils.len(), 3);
+            assert!(result.sigils.contains(&locked_sigil));
+            assert_eq!(result.wrightstone.as_ref(), Some(&locked_stone));
+        }
+    }
+
+    #[test]
+    fn finds_minimal_builds_with_empty_slots() {
+        let query = SearchQuery {
+            allow_empty_slots: true,
+            ..query(&[(TK::DMGCap, 30), (TK::Gamma, 15)], 5)
+        };
+
+        let naive = NaiveSolver.search(pool(), &query);
+        let branch_bound = BranchBoundSolver.search(pool(), &query);
+
+        assert!(!branch_bound.is_empty());
+        assert_eq!(naive.len(), branch_bound.len());
+        assert!(branch_bound.iter().any(|r| r.sigils.len() == 2));
+        for result in &branch_bound {
+            assert!(naive.contains(result));
+            assert!(crate::solver::util::is_minimal(result, &query));
+        }
+    }
+
+    #[test]
+    fn finds_minimal_builds_with_optional_traits() {
+        let query = SearchQuery {
+            optional_traits: vec![OptionalTrait {
+                kind: TK::Drain,
+                level: 15,
+                weight: 1,
+            }],
+            allow_empty_slots: true,
+            ..query(&[(TK::DMGCap, 30), (TK::CriticalHitRate, 20)], 5)
+        };
+
+        let naive = NaiveSolver.search(pool(), &query);
+        let branch_bound = BranchBoundSolver.search(pool(), &query);
+
+        assert_eq!(naive.len(), branch_bound.len());
+        assert!(branch_bound.iter().any(|r| r
+            .sigils
+            .iter()
+            .any(|s| s.trait2 == Some(Trait::new(TK::Drain, 15)))));
+        for result in &branch_bound {
+            assert!(naive.contains(result));
+        }
+    }
+
+    #[test]
+    fn more_locked_sigils_than_slots_has_no_results() {
+        let query = SearchQuery {
+            sigil_slots: 1,
+            locked_sigils: vec![pool().sigils[0].item.clone(); 2],
+            ..Default::default()
+        };
+
+        assert!(BranchBoundSolver.search(pool(), &query).is_empty());
+        assert!(NaiveSolver.search(pool(), &query).is_empty());
+    }
+
+    #[test]
+    fn locked_sigils_come_from_the_pool() {
+        let query = SearchQuery {
+            locked_sigils: vec![pool().sigils[0].item.clone(); 2],
+            ..query(&[(TK::Aegis, 15)], 3)
+        };
+
+        assert!(BranchBoundSolver.search(pool(), &query).is_empty());
+        assert!(NaiveSolver.search(pool(), &query).is_empty());
+    }
+
+    #[test]
+    fn identical_sigils_give_no_duplicate_results() {
+        let query = query(&[(TK::DMGCap, 45), (TK::Gamma, 11)], 4);
+
+        let results = BranchBoundSolver.search(pool(), &query);
+
+        assert!(!results.is_empty());
+        for (i, result) in results.iter().enumerate() {
+            assert!(!results[i + 1..].contains(result));
+        }
+        assert_eq!(results.len(), NaiveSolver.search(pool(), &query).len());
+    }
+
+    #[test]
+    fn impossible_query_has_no_results() {
+        let query = query(&[(TK::Gamma, 40)], 5);
+
+        assert!(BranchBoundSolver.search(pool(), &query).is_empty());
+    }
+
+    /// 300 random sigils, few of them with the traits the large pool tests want,
+    /// and the example wrightstones and weapons
+    fn large_pool() -> SearchPool {
+        let wanted = [
+            TK::DMGCap,
+            TK::Gamma,
+            TK::CriticalHitRate,
+            TK::SupplementaryDamage,
+            TK::Tyranny,
+        ];
+        let kinds: Vec<TK> = TK::all().filter(|kind| !kind.is_exclusive()).collect();
+        let mut rng = StdRng::seed_from_u64(4);
+        let pick = |rng: &mut StdRng| {
+            let kinds = if rng.gen_bool(0.05) {
+                &wanted[..]
+            } else {
+                &kinds
+            };
+            let kind = *kinds.choose(rng).unwrap();
+            Trait::new(kind, *[5, 10, 11, 15, 15].choose(rng).unwrap())
+        };
+        let sigils = (0..300)
+            .map(|_| {
+                let trait1 = pick(&mut rng);
+                let trait2 = rng.gen_bool(0.6).then(|| pick(&mut rng));
+                Sigil {
+                    trait1,
+                    trait2: trait2.filter(|t| t.kind != trait1.kind),
+                }
+            })
+            .collect();
+        let weapon = |name: &str, traits: &[(TK, u8)]| {
+            let mut traits = traits.iter().map(|&(k, l)| Trait::new(k, l));
+            Weapon {
+                name: name.to_string(),
+                trait1: traits.next().unwrap(),
+                trait2: traits.next(),
+                trait3: traits.next(),
+            }
+        };
+        let weapons = vec![
+            weapon("Ascalon", &[(TK::ATK, 15), (TK::CriticalHitRate, 10)]),
+            weapon(
+                "Eternal Sword",
+                &[(TK::DMGCap, 15), (TK::SupplementaryDamage, 10)],
+            ),
+            weapon(
+                "Ultima Sword",
+                &[(TK::Gamma, 10), (TK::DMGCap, 10), (TK::Tyranny, 5)],
+            ),
+        ];
+        let stone = |traits: [(TK, u8); 3]| {
+            let [t1, t2, t3] = traits.map(|(k, l)| Trait::new(k, l));
+            Wrightstone {
+                trait1: t1,
+                trait2: Some(t2),
+                trait3: Some(t3),
+            }
+        };
+        let wrightstones = vec![
+            stone([(TK::CriticalHitRate, 10), (TK::HP, 6), (TK::Uplift, 3)]),
+            stone([
+                (TK::CriticalHitRate, 9),
+                (TK::Tyranny, 7),
+                (TK::QuickCharge, 5),
+            ]),
+            stone([(TK::HP, 7), (TK::Autorevive, 5), (TK::Guts, 5)]),
+        ];
+        SearchPool::new(sigils, wrightstones, weapons)
+    }
+
+    #[test]
+    fn prunes_large_pool_at_feasibility_boundary() {
+        // Supplementary DMG 40 can still be reached
+        let query = query(
+            &[
+                (TK::DMGCap, 45),
+                (TK::Gamma, 30),
+                (TK::CriticalHitRate, 30),
+                (TK::SupplementaryDamage, 45),
+                (TK::Tyranny, 15),
+            ],
+            10,
+        );
+
+        let start = Instant::now();
+        let results = BranchBoundSolver.search_first(large_pool(), &query, 1);
+
+        assert!(results.is_empty());
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn finds_fewest_sigils_in_large_pool() {
+        let query = query(
+            &[
+                (TK::DMGCap, 45),
+                (TK::Gamma, 30),
+                (TK::CriticalHitRate, 30),
+                (TK::SupplementaryDamage, 30),
+                (TK::Tyranny, 15),
+            ],
+            12,
+        );
+
+        let start = Instant::now();
+        let smallest = BranchBoundSolver.search_fewest_sigils(large_pool(), &query, 3);
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert!(smallest.proven);
+        assert_eq!(smallest.results.len(), 3);
+        let fewest = smallest.results[0].sigils.len();
+        assert!(smallest.results.iter().all(|r| r.sigils.len() == fewest));
+        let smaller = SearchQuery {
+            sigil_slots: fewest as u8 - 1,
+            ..query
+        };
+        assert!(BranchBoundSolver
+            .search_first(large_pool(), &smaller, 1)
+            .is_empty());
+    }
+
+    #[test]
+    fn finds_minimal_builds_in_large_pool() {
+        let query = SearchQuery {
+            allow_empty_slots: true,
+            ..query(
+                &[
+                    (TK::DMGCap, 45),
+                    (TK::Gamma, 30),
+                    (TK::CriticalHitRate, 30),
+                    (TK::SupplementaryDamage, 40),
+                    (TK::Tyranny, 15),
+                ],
+                12,
+            )
+        };
+
+        let start = Instant::now();
+        let results = BranchBoundSolver.search_first(large_pool(), &query, 3);
+
+        assert!(start.elapsed() < Duration::from_secs(10));
+        assert_eq!(results.len(), 3);
+        for result in &results {
+            assert!(crate::solver::util::is_minimal(result, &query));
+        }
+    }
+}
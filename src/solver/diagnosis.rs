@@ -0,0 +1,491 @@
+use std::fmt::Display;
+
+use itertools::Itertools;
+
+use crate::model::{SearchPool, SearchQuery, SearchResult, Sigil, Trait, TraitKind, TraitSet};
+
+use super::util::{can_lock, choices, filter_pool, missing_locked_sigils};
+use super::{BranchBoundSolver, Solver};
+
+/// the reasons a query has no solution, found by [`diagnose`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnosis {
+    /// whether a build satisfies the query after all, in which case `closest` is that build
+    /// and no causes are looked for
+    pub satisfiable: bool,
+    /// the sigil slots not taken by locked sigils
+    pub sigil_slots: u8,
+    /// the number of locked sigils that do not fit into the sigil slots
+    pub excess_locked: usize,
+    /// the number of locked sigils the inventory has no copy of
+    pub missing_locked: usize,
+    /// desired traits out of reach even with every sigil in the pool, `achieved` being the
+    /// highest level the pool can reach
+    pub unreachable: Vec<Deficit>,
+    /// desired traits that take more sigils than the query has slots
+    pub too_few_slots: Vec<SlotShortage>,
+    /// pairs of desired traits that can each be reached within the slots, but not together
+    pub conflicts: Vec<(TraitKind, TraitKind)>,
+    /// the build found that comes closest to the desired levels
+    pub closest: SearchResult,
+    /// the desired traits the closest build falls short of
+    pub deficits: Vec<Deficit>,
+}
+
+/// a desired trait level that is not reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deficit {
+    pub kind: TraitKind,
+    pub desired: u16,
+    pub achieved: u16,
+}
+
+/// a desired trait that needs more sigils than there are slots
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotShortage {
+    pub kind: TraitKind,
+    pub sigils_needed: usize,
+}
+
+/// explains why no build satisfies `query`, after checking that none does.
+///
+/// traits are checked one at a time and in pairs, so a query can still be unsatisfiable
+/// when no problem is found, because of three or more traits competing for the slots
+pub fn diagnose(pool: SearchPool, query: &SearchQuery) -> Diagnosis {
+    let slots = query.unlocked_slots().unwrap_or(0);
+    let excess_locked = query
+        .locked_sigils
+        .len()
+        .saturating_sub(query.sigil_slots as usize);
+    let missing_locked = missing_locked_sigils(&pool, query);
+
+    if let Some(build) = BranchBoundSolver.search_first(pool.clone(), query, 1).pop() {
+        return Diagnosis {
+            satisfiable: true,
+            sigil_slots: slots as u8,
+            excess_locked,
+            missing_locked,
+            unreachable: Vec::new(),
+            too_few_slots: Vec::new(),
+            conflicts: Vec::new(),
+            closest: build,
+            deficits: Vec::new(),
+        };
+    }
+
+    // the solver takes the locked sigils from the pool itself
+    let unfiltered = pool.clone();
+    let pool = filter_pool(pool, query);
+
+    let mut unreachable = Vec::new();
+    let mut too_few_slots = Vec::new();
+    let mut feasible = Vec::new();
+    for kind in desired_kinds(query) {
+        let desired = query.desired_traits.effective_level(kind);
+        let (max_level, sigils_needed) = reach(&pool, query, kind);
+
+        match sigils_needed {
+            None => unreachable.push(Deficit {
+                kind,
+                desired,
+                achieved: max_level,
+            }),
+            Some(sigils_needed) if sigils_needed > slots => too_few_slots.push(SlotShortage {
+                kind,
+                sigils_needed,
+            }),
+            Some(_) => feasible.push(kind),
+        }
+    }
+
+    // with locks that cannot be satisfied every pair would look like a conflict
+    let conflicts = if !can_lock(&unfiltered, query) {
+        Vec::new()
+    } else {
+        feasible
+            .iter()
+            .tuple_combinations()
+            .filter(|&(&a, &b)| {
+                let mut desired_traits = TraitSet::new();
+                for kind in [a, b] {
+                    desired_traits.insert(kind, query.desired_traits.level(kind));
+                }
+                let pair = SearchQuery {
+                    desired_traits,
+                    ..query.clone()
+                };
+                BranchBoundSolver
+                    .search_first(unfiltered.clone(), &pair, 1)
+                    .is_empty()
+            })
+            .map(|(&a, &b)| (a, b))
+            .collect()
+    };
+
+    let closest = closest_build(&pool, query);
+    let deficits = deficits(&closest.traits(), query);
+
+    Diagnosis {
+        satisfiable: false,
+        sigil_slots: slots as u8,
+        excess_locked,
+        missing_locked,
+        unreachable,
+        too_few_slots,
+        conflicts,
+        closest,
+        deficits,
+    }
+}
+
+fn desired_kinds(query: &SearchQuery) -> Vec<TraitKind> {
+    query
+        .desired_traits
+        .iter()
+        .map(|(&kind, _)| kind)
+        .sorted()
+        .collect()
+}
+
+/// the highest level of `kind` the pool can reach, and the fewest sigils that reach
+/// the desired level together with the locked sigils and the best wrightstone and weapon
+/// for it, if any number does
+fn reach(pool: &SearchPool, query: &SearchQuery, kind: TraitKind) -> (u16, Option<usize>) {
+    let desired = query.desired_traits.effective_level(kind);
+    let locked = level_of(kind, query.locked_sigils.iter().flat_map(Sigil::traits));
+    let wrightstone = pool
+        .wrightstones
+        .iter()
+        .map(|stack| level_of(kind, stack.item.traits()))
+        .max()
+        .unwrap_or(0);
+    let weapon = pool
+        .weapons
+        .iter()
+        .map(|weapon| level_of(kind, weapon.traits()))
+        .max()
+        .unwrap_or(0);
+
+    let sigil_levels = pool
+        .sigils
+        .iter()
+        .flat_map(|stack| {
+            std::iter::repeat_n(level_of(kind, stack.item.traits()), stack.count.into())
+        })
+        .filter(|&level| level > 0)
+        .sorted_unstable_by(|a, b| b.cmp(a));
+
+    let mut level = locked + wrightstone + weapon;
+    let mut sigils_needed = (level >= desired).then_some(0);
+    for (i, sigil_level) in sigil_levels.enumerate() {
+        level += sigil_level;
+        if sigils_needed.is_none() && level >= desired {
+            sigils_needed = Some(i + 1);
+        }
+    }
+
+    (level, sigils_needed)
+}
+
+fn level_of(kind: TraitKind, traits: impl Iterator<Item = Trait>) -> u16 {
+    traits
+        .filter(|t| t.kind == kind)
+        .map(|t| t.level as u16)
+        .sum()
+}
+
+/// the sum of the missing levels of every desired trait
+fn total_deficit(traits: &TraitSet, query: &SearchQuery) -> u16 {
+    query
+        .desired_traits
+        .iter()
+        .map(|(&kind, _)| {
+            query
+                .desired_traits
+                .effective_level(kind)
+                .saturating_sub(traits.effective_level(kind))
+        })
+        .sum()
+}
+
+fn deficits(traits: &TraitSet, query: &SearchQuery) -> Vec<Deficit> {
+    desired_kinds(query)
+        .into_iter()
+        .map(|kind| Deficit {
+            kind,
+            desired: query.desired_traits.effective_level(kind),
+            achieved: traits.effective_level(kind),
+        })
+        .filter(|deficit| deficit.achieved < deficit.desired)
+        .collect()
+}
+
+/// greedily fills the slots left by the locked sigils with the sigil that closes the most
+/// missing levels without going above a max level,
+/// for every choice of wrightstone and weapon, and keeps the build missing the fewest levels
+fn closest_build(pool: &SearchPool, query: &SearchQuery) -> SearchResult {
+    let wrightstones = pool.wrightstones.iter().map(|stack| &stack.item).collect();
+    let weapons = pool.weapons.iter().collect();
+
+    choices(wrightstones)
+        .into_iter()
+        .cartesian_product(choices(weapons))
+        .map(|(wrightstone, weapon)| {
+            let locked = query.locked_sigils.clone();
+            let mut build = SearchResult::new(locked, wrightstone.cloned(), weapon.cloned());
+            let mut left: Vec<u16> = pool.sigils.iter().map(|stack| stack.count).collect();
+
+            for _ in 0..query.unlocked_slots().unwrap_or(0) {
+                let deficit = total_deficit(&build.traits(), query);
+                let best = pool
+                    .sigils
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| left[i] > 0)
+                    .map(|(i, stack)| (i, traits_with(&build, &stack.item)))
+                    .filter(|(_, traits)| !query.exceeds_max_levels(traits))
+                    .map(|(i, traits)| (i, deficit - total_deficit(&traits, query)))
+                    .filter(|&(_, closed)| closed > 0)
+                    .max_by_key(|&(i, closed)| (closed, std::cmp::Reverse(i)));
+
+                let Some((i, _)) = best else {
+                    break;
+                };
+                left[i] -= 1;
+                build.sigils.push(pool.sigils[i].item.clone());
+            }
+
+            let deficit = total_deficit(&build.traits(), query);
+            (deficit, build)
+        })
+        .min_by_key(|(deficit, _)| *deficit)
+        .map(|(_, build)| SearchResult::new(build.sigils, build.wrightstone, build.weapon))
+        .expect("there is always at least one choice of wrightstone and weapon")
+}
+
+fn traits_with(build: &SearchResult, sigil: &Sigil) -> TraitSet {
+    let mut traits = build.traits();
+    for t in sigil.traits() {
+        traits.add(t);
+    }
+    traits
+}
+
+impl Diagnosis {
+    /// whether any cause was found for the query having no solution
+    pub fn found_cause(&self) -> bool {
+        self.excess_locked > 0
+            || self.missing_locked > 0
+            || !self.unreachable.is_empty()
+            || !self.too_few_slots.is_empty()
+            || !self.conflicts.is_empty()
+    }
+}
+
+impl Display for Diagnosis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.satisfiable {
+            return write!(
+                f,
+                "The query is satisfiable, the build below reaches every desired level."
+            );
+        }
+
+        if self.excess_locked > 0 {
+            writeln!(
+                f,
+                "{} more sigils are locked than there are sigil slots.",
+                self.excess_locked
+            )?;
+        }
+
+        if self.missing_locked > 0 {
+            writeln!(
+                f,
+                "{} locked sigils are not in the inventory.",
+                self.missing_locked
+            )?;
+        }
+
+        if !self.unreachable.is_empty() {
+            writeln!(f, "Out of reach with every sigil in the inventory:")?;
+            for deficit in &self.unreachable {
+                writeln!(
+                    f,
+                    "  {}: {} desired, at most {}",
+                    deficit.kind, deficit.desired, deficit.achieved
+                )?;
+            }
+        }
+
+        if !self.too_few_slots.is_empty() {
+            writeln!(f, "Needs more than {} sigil slots:", self.sigil_slots)?;
+            for shortage in &self.too_few_slots {
+                writeln!(f, "  {}: {} sigils", shortage.kind, shortage.sigils_needed)?;
+            }
+        }
+
+        if !self.conflicts.is_empty() {
+            writeln!(
+                f,
+                "Cannot be reached together within {} sigil slots:",
+                self.sigil_slots
+            )?;
+            for (a, b) in &self.conflicts {
+                writeln!(f, "  {a} and {b}")?;
+            }
+        }
+
+        if !self.found_cause() && !self.deficits.is_empty() {
+            writeln!(
+                f,
+                "Every desired trait can be reached alone and in pairs, \
+                but not all of them together."
+            )?;
+        }
+
+        if self.deficits.is_empty() {
+            write!(f, "The closest build found reaches every desired level.")
+        } else {
+            let missing = self
+                .deficits
+                .iter()
+                .map(|d| format!("{} {} of {}", d.kind, d.achieved, d.desired))
+                .join(", ");
+            write!(f, "The closest build found reaches {missing}.")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{TraitKind as TK, Wrightstone};
+    use crate::solver::testing::query;
+
+    use super::*;
+
+    fn pool() -> SearchPool {
+        let sigil = |kind, level, count| {
+            std::iter::repeat_n(Sigil::new_single(Trait::new(kind, level)), count)
+        };
+
+        SearchPool::new(
+            sigil(TK::DMGCap, 15, 3)
+                .chain(sigil(TK::Gamma, 15, 2))
+                .chain(sigil(TK::CriticalHitRate, 10, 2))
+                .chain(sigil(TK::Aegis, 15, 1))
+                .collect(),
+            vec![Wrightstone {
+                trait1: Trait::new(TK::CriticalHitRate, 10),
+                trait2: None,
+                trait3: None,
+            }],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn finds_unreachable_traits() {
+        let diagnosis = diagnose(pool(), &query(&[(TK::DMGCap, 65), (TK::Aegis, 15)], 12));
+
+        assert_eq!(
+            diagnosis.unreachable,
+            [Deficit {
+                kind: TK::DMGCap,
+                desired: 65,
+                achieved: 45
+            }]
+        );
+        assert!(diagnosis.too_few_slots.is_empty());
+    }
+
+    #[test]
+    fn counts_sigils_needed_including_the_wrightstone() {
+        let diagnosis = diagnose(pool(), &query(&[(TK::CriticalHitRate, 30)], 1));
+
+        assert_eq!(
+            diagnosis.too_few_slots,
+            [SlotShortage {
+                kind: TK::CriticalHitRate,
+                sigils_needed: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn locked_sigils_take_up_slots() {
+        let mut q = query(&[(TK::DMGCap, 45)], 2);
+        q.locked_sigils = vec![Sigil::new_single(Trait::new(TK::DMGCap, 15))];
+
+        let diagnosis = diagnose(pool(), &q);
+
+        assert_eq!(diagnosis.sigil_slots, 1);
+        assert_eq!(
+            diagnosis.too_few_slots,
+            [SlotShortage {
+                kind: TK::DMGCap,
+                sigils_needed: 2
+            }]
+        );
+        assert!(diagnosis.closest.sigils.contains(&q.locked_sigils[0]));
+    }
+
+    #[test]
+    fn satisfiable_query_has_no_causes() {
+        let diagnosis = diagnose(pool(), &query(&[(TK::DMGCap, 30), (TK::Aegis, 15)], 3));
+
+        assert!(diagnosis.satisfiable);
+        assert!(!diagnosis.found_cause());
+        assert!(diagnosis.deficits.is_empty());
+        assert_eq!(diagnosis.closest.sigils.len(), 3);
+        assert!(diagnosis
+            .to_string()
+            .starts_with("The query is satisfiable"));
+    }
+
+    #[test]
+    fn locked_sigils_have_to_be_in_the_pool() {
+        let mut q = query(&[(TK::DMGCap, 15)], 3);
+        q.locked_sigils = vec![Sigil::new_single(Trait::new(TK::Aegis, 15)); 2];
+
+        let diagnosis = diagnose(pool(), &q);
+
+        assert!(!diagnosis.satisfiable);
+        assert_eq!(diagnosis.missing_locked, 1);
+        assert!(diagnosis.found_cause());
+        assert!(diagnosis.conflicts.is_empty());
+    }
+
+    #[test]
+    fn conflicts_leave_the_only_copy_of_a_locked_sigil_in_the_pool() {
+        let mut q = query(&[(TK::DMGCap, 30), (TK::Gamma, 15), (TK::Aegis, 15)], 3);
+        q.locked_sigils = vec![Sigil::new_single(Trait::new(TK::Aegis, 15))];
+
+        let diagnosis = diagnose(pool(), &q);
+
+        assert!(!diagnosis.satisfiable);
+        assert_eq!(diagnosis.conflicts, [(TK::DMGCap, TK::Gamma)]);
+    }
+
+    #[test]
+    fn finds_conflicting_pairs() {
+        let q = query(&[(TK::DMGCap, 45), (TK::Gamma, 15), (TK::Aegis, 15)], 3);
+
+        let diagnosis = diagnose(pool(), &q);
+
+        assert!(diagnosis.unreachable.is_empty() && diagnosis.too_few_slots.is_empty());
+        assert_eq!(
+            diagnosis.conflicts,
+            [(TK::Aegis, TK::DMGCap), (TK::DMGCap, TK::Gamma)]
+        );
+        assert_eq!(diagnosis.closest.sigils.len(), 3);
+        assert_eq!(
+            diagnosis
+                .deficits
+                .iter()
+                .map(|d| d.desired - d.achieved)
+                .sum::<u16>(),
+            30
+        );
+    }
+}
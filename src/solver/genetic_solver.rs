@@ -1,10 +1,22 @@
+use std::ops::ControlFlow;
+
+use itertools::Itertools;
 use rand::prelude::*;
 
-use crate::model::{SearchPool, SearchQuery, SearchResult};
+use crate::model::{SearchPool, SearchQuery, SearchResult, Sigil, Stack, Weapon, Wrightstone};
 
-use super::util::{filter_pool, is_valid_result};
+use super::util::{can_lock, filter_pool, is_valid_result, minimize};
 use super::Solver;
 
+/// weight applied to every desired trait level that is still missing
+const DEFICIT_WEIGHT: i32 = 4;
+
+/// weight applied to every trait level above the desired level
+const OVERSHOOT_WEIGHT: i32 = 1;
+
+/// weight applied to every trait level above a trait's max level, which has no effect
+const WASTE_WEIGHT: i32 = 2;
+
 /// a solver using a genetic algorithm
 pub struct GeneticSolver {
     pub population_size: usize,
@@ -15,43 +27,84 @@ pub struct GeneticSolver {
 }
 
 impl Solver for GeneticSolver {
-    fn search(&self, pool: SearchPool, query: &SearchQuery) -> Vec<SearchResult> {
-        let filtered_pool = filter_pool(pool, query);
+    /// the population is only checked for valid results after the last generation,
+    /// so results are passed to `sink` once the search has finished
+    fn search_each(
+        &self,
+        pool: SearchPool,
+        query: &SearchQuery,
+        sink: &mut dyn FnMut(SearchResult) -> ControlFlow<()>,
+    ) {
+        if !can_lock(&pool, query) {
+            return;
+        }
+
+        let inventory = Inventory::new(filter_pool(pool, query));
         let mut population = Population::new(
             self.population_size,
             self.tournament_size,
             self.crossover_rate,
             self.mutation_rate,
-            &filtered_pool,
+            &inventory,
             query,
         );
-        dbg!(&population);
 
-        for i in 0..self.generations {
-            println!("running generation {i}");
+        for _ in 0..self.generations {
             population.next_generation();
         }
 
-        population.valid_results(query)
+        let _ = population
+            .valid_results(query)
+            .into_iter()
+            .try_for_each(sink);
     }
 }
 
+/// every genome starts with the query's locked sigils, which are never crossed over,
+/// mutated or repaired
 #[derive(Debug)]
 struct Population<'s> {
     genomes: Vec<SearchResult>,
     tournament_size: usize,
     crossover_rate: f64,
     mutation_rate: f64,
+    pool: &'s Inventory,
     query: &'s SearchQuery,
 }
 
+/// the filtered pool with every stack expanded into its individual items,
+/// so identical items can be picked as often as they are owned
+#[derive(Debug)]
+struct Inventory {
+    sigils: Vec<Sigil>,
+    wrightstones: Vec<Wrightstone>,
+    weapons: Vec<Weapon>,
+}
+
+impl Inventory {
+    fn new(pool: SearchPool) -> Self {
+        Inventory {
+            sigils: unstack(pool.sigils),
+            wrightstones: unstack(pool.wrightstones),
+            weapons: pool.weapons,
+        }
+    }
+}
+
+fn unstack<T: Clone>(stacks: Vec<Stack<T>>) -> Vec<T> {
+    stacks
+        .into_iter()
+        .flat_map(|stack| std::iter::repeat_n(stack.item, stack.count as usize))
+        .collect()
+}
+
 impl<'s> Population<'s> {
     fn new(
         size: usize,
         tournament_size: usize,
         crossover_rate: f64,
         mutation_rate: f64,
-        pool: &SearchPool,
+        pool: &'s Inventory,
         query: &'s SearchQuery,
     ) -> Self {
         let mut rng = rand::thread_rng();
@@ -62,25 +115,53 @@ impl<'s> Population<'s> {
             tournament_size,
             crossover_rate,
             mutation_rate,
+            pool,
             query,
         }
     }
 
+    /// the valid genomes in canonical form, as the population often holds the same build
+    /// several times. genomes always fill every slot, so sigils they do not need are left
+    /// out if the query allows empty slots
     fn valid_results(self, query: &SearchQuery) -> Vec<SearchResult> {
         self.genomes
             .into_iter()
             .filter(|res| is_valid_result(res, query))
+            .map(|res| {
+                if query.allow_empty_slots {
+                    minimize(res, query)
+                } else {
+                    res
+                }
+            })
+            .map(|res| SearchResult::new(res.sigils, res.wrightstone, res.weapon))
+            .unique()
             .collect()
     }
 
     fn next_generation(&mut self) {
+        if self.genomes.is_empty() {
+            return;
+        }
+
         let mut rng = rand::thread_rng();
+        let elite = self.fittest().clone();
         let parents = self.select(&mut rng);
         let mut children = self.crossover(&parents, &mut rng);
         self.mutate(&mut children, &mut rng);
+
+        // keep the best genome of the previous generation so progress is never lost
+        children[0] = elite;
         self.genomes = children;
     }
 
+    fn fittest(&self) -> &SearchResult {
+        self.genomes
+            .iter()
+            .max_by_key(|g| fitness(g, self.query))
+            .unwrap()
+    }
+
     fn select(&self, rng: &mut ThreadRng) -> Vec<&SearchResult> {
         let len = self.genomes.len();
         let mut parents = Vec::with_capacity(len);
@@ -89,7 +170,7 @@ impl<'s> Population<'s> {
             let best = self
                 .genomes
                 .choose_multiple(rng, self.tournament_size)
-                .max_by_key(|g| fitness(&g, self.query))
+                .max_by_key(|g| fitness(g, self.query))
                 .unwrap();
             parents.push(best);
 
@@ -109,10 +190,10 @@ impl<'s> Population<'s> {
             let parent2 = parents[1];
             assert_eq!(parent1.sigils.len(), parent2.sigils.len());
             let len = parent1.sigils.len();
+            let locked = self.query.locked_sigils.len();
 
-            // TODO: crossover wrightstones
-            if rng.gen_bool(self.crossover_rate) {
-                let point = rng.gen_range(0..len);
+            if len > locked && rng.gen_bool(self.crossover_rate) {
+                let point = rng.gen_range(locked..len);
                 let p1_1 = &parent1.sigils[..point];
                 let p1_2 = &parent1.sigils[point..];
                 let p2_1 = &parent2.sigils[..point];
@@ -121,46 +202,251 @@ impl<'s> Population<'s> {
                 let child1_sigils = [p1_1, p2_2].into_iter().flatten().cloned().collect();
                 let child2_sigils = [p2_1, p1_2].into_iter().flatten().cloned().collect();
 
-                children.push(SearchResult {
+                // the wrightstones are swapped along with the second half,
+                // while each child keeps the weapon of its first half
+                let mut child1 = SearchResult {
                     sigils: child1_sigils,
-                    wrightstone: parent1.wrightstone.clone(),
-                });
-                children.push(SearchResult {
-                    sigils: child2_sigils,
                     wrightstone: parent2.wrightstone.clone(),
-                });
+                    weapon: parent1.weapon.clone(),
+                };
+                let mut child2 = SearchResult {
+                    sigils: child2_sigils,
+                    wrightstone: parent1.wrightstone.clone(),
+                    weapon: parent2.weapon.clone(),
+                };
+                self.repair(&mut child1, rng);
+                self.repair(&mut child2, rng);
+
+                children.push(child1);
+                children.push(child2);
             } else {
                 children.push(parent1.clone());
                 children.push(parent2.clone());
             }
         }
 
+        // an odd population leaves one parent without a partner
+        if let [.., last] = parents {
+            if children.len() < parents.len() {
+                children.push((*last).clone());
+            }
+        }
+
         children
     }
 
-    fn mutate(&self, children: &mut Vec<SearchResult>, rng: &mut ThreadRng) -> Vec<SearchResult> {
-        // TODO: find a way to randomly select unused sigils from the pool
-        todo!()
+    /// replace each sigil with a random unused one from the pool with a chance of `mutation_rate`,
+    /// and pick a new random wrightstone and weapon with the same chance
+    fn mutate(&self, children: &mut [SearchResult], rng: &mut ThreadRng) {
+        for child in children {
+            for i in self.query.locked_sigils.len()..child.sigils.len() {
+                if !rng.gen_bool(self.mutation_rate) {
+                    continue;
+                }
+                if let Some(sigil) = unused_sigils(self.pool, &child.sigils).choose(rng) {
+                    child.sigils[i] = (*sigil).clone();
+                }
+            }
+
+            if rng.gen_bool(self.mutation_rate) {
+                child.wrightstone = self.pool.wrightstones.choose(rng).cloned();
+            }
+
+            if rng.gen_bool(self.mutation_rate) {
+                child.weapon = self.pool.weapons.choose(rng).cloned();
+            }
+        }
+    }
+
+    /// crossover can combine the same pool sigil from both parents, so replace any sigils
+    /// that are used more often than they appear in the pool with unused ones
+    fn repair(&self, child: &mut SearchResult, rng: &mut ThreadRng) {
+        let mut available: Vec<&Sigil> = self.pool.sigils.iter().collect();
+        let mut excess = Vec::new();
+
+        let locked = self.query.locked_sigils.len();
+        for (i, sigil) in child.sigils.iter().enumerate().skip(locked) {
+            match available.iter().position(|s| *s == sigil) {
+                Some(pos) => {
+                    available.swap_remove(pos);
+                }
+                None => excess.push(i),
+            }
+        }
+
+        for i in excess {
+            let pos = rng.gen_range(0..available.len());
+            child.sigils[i] = available.swap_remove(pos).clone();
+        }
     }
 }
 
-fn random_combination(pool: &SearchPool, query: &SearchQuery, rng: &mut ThreadRng) -> SearchResult {
-    let sigils = pool
-        .sigils
-        .choose_multiple(rng, query.sigil_slots as usize)
+/// returns the sigils in the pool that are not already part of `used`,
+/// counting identical sigils separately
+fn unused_sigils<'p>(pool: &'p Inventory, used: &[Sigil]) -> Vec<&'p Sigil> {
+    let mut remaining: Vec<&Sigil> = used.iter().collect();
+    let mut unused = Vec::new();
+
+    for sigil in &pool.sigils {
+        match remaining.iter().position(|s| *s == sigil) {
+            Some(pos) => {
+                remaining.swap_remove(pos);
+            }
+            None => unused.push(sigil),
+        }
+    }
+
+    unused
+}
+
+fn random_combination(pool: &Inventory, query: &SearchQuery, rng: &mut ThreadRng) -> SearchResult {
+    let unlocked_slots = query.unlocked_slots().unwrap_or(0);
+    let sigils = query
+        .locked_sigils
+        .iter()
+        .chain(pool.sigils.choose_multiple(rng, unlocked_slots))
         .cloned()
         .collect();
     let wrightstone = pool.wrightstones.choose(rng).cloned();
+    let weapon = pool.weapons.choose(rng).cloned();
 
     SearchResult {
         sigils,
         wrightstone,
+        weapon,
     }
 }
 
+/// quantifies how well a search result fulfills the query, higher is better.
+///
+/// every missing level of a desired trait costs `DEFICIT_WEIGHT` and every level above the
+/// desired level costs `OVERSHOOT_WEIGHT`, so results that do not meet the requirements
+/// are still ordered by how close they are. levels above a trait's max level are wasted
+/// and cost `WASTE_WEIGHT` on top, and levels above a max level of the query cost
+/// `DEFICIT_WEIGHT` like missing ones. a result that exactly fulfills the query scores 0.
 fn fitness(result: &SearchResult, query: &SearchQuery) -> i32 {
-    // TODO: create an algorithm that can quantify how well a search result fulfills the query
-    // must also give a positive score to results that dont meet the requirements
-    // so that they can be sorted
-    todo!()
+    let traits = result.traits();
+
+    let distance: i32 = query
+        .desired_traits
+        .iter()
+        .map(|(&kind, _)| {
+            let level = traits.effective_level(kind) as i32;
+            let desired = query.desired_traits.effective_level(kind) as i32;
+            let deficit = (desired - level).max(0);
+            let overshoot = (level - desired).max(0);
+            -(deficit * DEFICIT_WEIGHT) - overshoot * OVERSHOOT_WEIGHT
+        })
+        .sum();
+
+    let excess: i32 = query
+        .max_levels
+        .iter()
+        .map(|(&kind, &max)| traits.level(kind).saturating_sub(max) as i32)
+        .sum();
+
+    distance - excess * DEFICIT_WEIGHT - traits.wasted_levels() as i32 * WASTE_WEIGHT
+        + query.optional_score(&traits) as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Trait, TraitKind as TK, TraitSet};
+    use crate::solver::testing::query;
+
+    use super::*;
+
+    /// a build of single-trait sigils without wrightstone or weapon
+    fn build(sigils: &[(TK, u8)]) -> SearchResult {
+        let sigils = sigils
+            .iter()
+            .map(|&(k, l)| Sigil::new_single(Trait::new(k, l)))
+            .collect();
+        SearchResult::new(sigils, None, None)
+    }
+
+    #[test]
+    fn fitness_rewards_partial_progress() {
+        let query = query(&[(TK::DMGCap, 30)], 2);
+        let empty = build(&[]);
+        let half = build(&[(TK::DMGCap, 15)]);
+        let full = build(&[(TK::DMGCap, 15), (TK::DMGCap, 15)]);
+
+        assert!(fitness(&empty, &query) < fitness(&half, &query));
+        assert!(fitness(&half, &query) < fitness(&full, &query));
+        assert_eq!(fitness(&full, &query), 0);
+    }
+
+    #[test]
+    fn fitness_penalizes_overshoot() {
+        let query = query(&[(TK::DMGCap, 15)], 2);
+        let exact = build(&[(TK::DMGCap, 15)]);
+        let over = build(&[(TK::DMGCap, 15), (TK::DMGCap, 15)]);
+
+        assert!(fitness(&over, &query) < fitness(&exact, &query));
+    }
+
+    #[test]
+    fn fitness_penalizes_wasted_levels() {
+        let query = query(&[(TK::Aegis, 15)], 2);
+        let exact = build(&[(TK::Aegis, 15)]);
+        let over = build(&[(TK::Aegis, 15), (TK::Aegis, 15)]);
+
+        assert_eq!(fitness(&exact, &query), 0);
+        assert_eq!(fitness(&over, &query), -15 * WASTE_WEIGHT);
+    }
+
+    #[test]
+    fn fitness_penalizes_levels_above_max() {
+        let mut query = query(&[(TK::DMGCap, 15)], 1);
+        query.max_levels.insert(TK::GlassCannon, 0);
+        let plain = build(&[(TK::DMGCap, 15)]);
+        let forbidden = SearchResult::new(
+            vec![Sigil {
+                trait1: Trait::new(TK::DMGCap, 15),
+                trait2: Some(Trait::new(TK::GlassCannon, 15)),
+            }],
+            None,
+            None,
+        );
+
+        assert_eq!(fitness(&plain, &query), 0);
+        assert_eq!(fitness(&forbidden, &query), -15 * DEFICIT_WEIGHT);
+    }
+
+    #[test]
+    fn converges_on_large_pool() {
+        let mut sigils: Vec<Sigil> = (0..300)
+            .map(|i| match i % 3 {
+                0 => Sigil::new_single(Trait::new(TK::Aegis, 15)),
+                1 => Sigil::new_single(Trait::new(TK::Drain, 15)),
+                _ => Sigil::new_single(Trait::new(TK::Regen, 15)),
+            })
+            .collect();
+        sigils.push(Sigil {
+            trait1: Trait::new(TK::DMGCap, 15),
+            trait2: Some(Trait::new(TK::Gamma, 15)),
+        });
+        sigils.push(Sigil::new_single(Trait::new(TK::DMGCap, 15)));
+        sigils.push(Sigil::new_single(Trait::new(TK::Gamma, 15)));
+
+        let pool = SearchPool::new(sigils, vec![], vec![]);
+        let query = query(&[(TK::DMGCap, 30), (TK::Gamma, 30), (TK::Aegis, 15)], 4);
+        let solver = GeneticSolver {
+            population_size: 100,
+            generations: 200,
+            tournament_size: 3,
+            crossover_rate: 0.6,
+            mutation_rate: 0.1,
+        };
+
+        let results = solver.search(pool, &query);
+
+        assert!(!results.is_empty());
+        assert!(results.iter().all_unique());
+        for result in results {
+            let traits: TraitSet = result.traits();
+            assert!(traits.is_superset_of(&query.desired_traits));
+        }
+    }
 }
@@ -1,14 +1,118 @@
 // inspired by https://github.com/TanukiSharp/MHArmory/blob/master/MHArmory.Search/Documentation/README.md
 
+use std::ops::ControlFlow;
+
 use crate::model::{SearchPool, SearchQuery, SearchResult};
 
+mod branch_bound_solver;
+mod diagnosis;
 mod genetic_solver;
 mod naive_solver;
+mod objective;
+#[cfg(test)]
+mod testing;
 pub mod util;
 
+pub use branch_bound_solver::BranchBoundSolver;
+pub use diagnosis::{diagnose, Deficit, Diagnosis, SlotShortage};
 pub use genetic_solver::GeneticSolver;
 pub use naive_solver::NaiveSolver;
+pub use objective::{Objective, SmallestBuilds};
+
+use objective::TopK;
 
 pub trait Solver {
-    fn search(&self, pool: SearchPool, query: &SearchQuery) -> Vec<SearchResult>;
+    /// whether the solver finds every valid result, rather than an approximation
+    fn is_exact(&self) -> bool {
+        false
+    }
+
+    /// passes every valid result to `sink` as soon as it is found.
+    /// the search stops early once `sink` returns `ControlFlow::Break`.
+    fn search_each(
+        &self,
+        pool: SearchPool,
+        query: &SearchQuery,
+        sink: &mut dyn FnMut(SearchResult) -> ControlFlow<()>,
+    );
+
+    /// collects every valid result
+    fn search(&self, pool: SearchPool, query: &SearchQuery) -> Vec<SearchResult> {
+        let mut results = Vec::new();
+        self.search_each(pool, query, &mut |result| {
+            results.push(result);
+            ControlFlow::Continue(())
+        });
+        results
+    }
+
+    /// collects the first `limit` valid results and stops searching afterwards
+    fn search_first(
+        &self,
+        pool: SearchPool,
+        query: &SearchQuery,
+        limit: usize,
+    ) -> Vec<SearchResult> {
+        let mut results = Vec::new();
+        if limit == 0 {
+            return results;
+        }
+
+        self.search_each(pool, query, &mut |result| {
+            results.push(result);
+            if results.len() < limit {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        });
+        results
+    }
+
+    /// collects the `k` best valid results under `objective`, ordered from best to worst
+    fn search_best(
+        &self,
+        pool: SearchPool,
+        query: &SearchQuery,
+        objective: Objective,
+        k: usize,
+    ) -> Vec<SearchResult> {
+        let mut best = TopK::new(k);
+        self.search_each(pool, query, &mut |result| {
+            best.push(objective.score(&result, query), result);
+            ControlFlow::Continue(())
+        });
+        best.into_sorted_vec()
+    }
+
+    /// collects the `k` builds with the fewest sigils, ties broken by total surplus.
+    /// builds are searched by increasing number of sigils, so exact solvers prove that no
+    /// smaller build exists by finding none
+    fn search_fewest_sigils(
+        &self,
+        pool: SearchPool,
+        query: &SearchQuery,
+        k: usize,
+    ) -> SmallestBuilds {
+        let locked = query.locked_sigils.len();
+        for sigil_slots in locked..=query.sigil_slots as usize {
+            let sized = SearchQuery {
+                sigil_slots: sigil_slots as u8,
+                allow_empty_slots: false,
+                ..query.clone()
+            };
+            let results = self.search_best(pool.clone(), &sized, Objective::FreeSlots, k);
+            if !results.is_empty() {
+                return SmallestBuilds {
+                    results,
+                    proven: self.is_exact(),
+                };
+            }
+        }
+
+        SmallestBuilds {
+            results: Vec::new(),
+            proven: self.is_exact(),
+        }
+    }
 }
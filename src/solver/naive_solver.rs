@@ -1,54 +1,70 @@
+use std::ops::ControlFlow;
+
 use itertools::Itertools;
 
 use crate::model::{SearchPool, SearchQuery, SearchResult};
 
-use super::util::{filter_pool, is_valid_result};
+use super::util::{
+    can_lock, choices, filter_pool, is_minimal, is_valid_result, multiset_combinations,
+};
 use super::Solver;
 
 /// a naive solver using a brute force algorithm
 pub struct NaiveSolver;
 
 impl Solver for NaiveSolver {
-    fn search(&self, pool: SearchPool, query: &SearchQuery) -> Vec<SearchResult> {
+    fn is_exact(&self) -> bool {
+        true
+    }
+
+    fn search_each(
+        &self,
+        pool: SearchPool,
+        query: &SearchQuery,
+        sink: &mut dyn FnMut(SearchResult) -> ControlFlow<()>,
+    ) {
+        if !can_lock(&pool, query) {
+            return;
+        }
+
         let filtered_pool = filter_pool(pool, query);
-        let combinations = all_combinations(filtered_pool, query);
-        println!("Checking {} combinations...", combinations.len());
-        let valid = combinations
-            .into_iter()
+        let _ = all_combinations(filtered_pool, query)
             .filter(|c| is_valid_result(c, query))
-            .collect();
-        valid
+            .filter(|c| !query.allow_empty_slots || is_minimal(c, query))
+            .try_for_each(sink);
     }
 }
 
-fn all_combinations(pool: SearchPool, query: &SearchQuery) -> Vec<SearchResult> {
-    let num_sigils = std::cmp::min(query.sigil_slots as usize, pool.sigils.len());
+/// lazily generates every combination, so the search space never has to fit in memory.
+/// identical sigils are interchangeable, so each multiset of sigils is only generated once.
+/// the locked sigils are part of every combination, and combinations leave slots empty
+/// if the query allows it
+fn all_combinations(pool: SearchPool, query: &SearchQuery) -> impl Iterator<Item = SearchResult> {
+    let unlocked_slots = query.unlocked_slots().unwrap_or(0);
+    let num_sigils = std::cmp::min(unlocked_slots, pool.sigil_count());
+    let min_sigils = if query.allow_empty_slots {
+        0
+    } else {
+        num_sigils
+    };
+    let locked = query.locked_sigils.clone();
 
     let SearchPool {
         sigils,
         wrightstones,
+        weapons,
     } = pool;
 
-    let combinations = if wrightstones.len() > 0 {
-        sigils
-            .into_iter()
-            .combinations(num_sigils)
-            .cartesian_product(wrightstones.into_iter())
-            .map(|(sigils, wrightstone)| SearchResult {
-                sigils,
-                wrightstone: Some(wrightstone),
-            })
-            .collect()
-    } else {
-        sigils
-            .into_iter()
-            .combinations(num_sigils)
-            .map(|sigils| SearchResult {
-                sigils,
-                wrightstone: None,
-            })
-            .collect()
-    };
+    let counts: Vec<u16> = sigils.iter().map(|stack| stack.count).collect();
+    let wrightstones = wrightstones.into_iter().map(|stack| stack.item).collect();
 
-    combinations
+    (min_sigils..=num_sigils)
+        .flat_map(move |k| multiset_combinations(counts.clone(), k))
+        .map(move |picked| {
+            let picked = picked.iter().map(|&i| sigils[i].item.clone());
+            locked.iter().cloned().chain(picked).collect()
+        })
+        .cartesian_product(choices(wrightstones))
+        .cartesian_product(choices(weapons))
+        .map(|((sigils, wrightstone), weapon)| SearchResult::new(sigils, wrightstone, weapon))
 }
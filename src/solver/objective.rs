@@ -0,0 +1,292 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use crate::model::{SearchQuery, SearchResult};
+
+use super::util::is_desired_trait;
+
+/// decides which of several valid results is better
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    /// the most trait levels above the desired levels, summed over all desired traits
+    TotalSurplus,
+    /// the most sigil trait slots that are empty or hold a trait that was not desired,
+    /// leaving room to swap in something else
+    FreeTraitSlots,
+    /// the highest combined level of traits that are not part of the query
+    BonusTraits,
+    /// the fewest trait levels above the traits' max levels, which have no effect
+    LeastWaste,
+    /// the highest weighted level of the query's optional traits
+    OptionalTraits,
+    /// the fewest sigils used, leaving the most slots free, and then the highest
+    /// total surplus
+    FreeSlots,
+}
+
+/// the builds with the fewest sigils found by [`super::Solver::search_fewest_sigils`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmallestBuilds {
+    /// the best builds by total surplus among the builds with the fewest sigils,
+    /// ordered from best to worst
+    pub results: Vec<SearchResult>,
+    /// whether every smaller build was ruled out by an exact solver, which proves that
+    /// no build with fewer sigils satisfies the query
+    pub proven: bool,
+}
+
+impl Objective {
+    /// scores a result under this objective, higher is better
+    pub fn score(&self, result: &SearchResult, query: &SearchQuery) -> i64 {
+        match self {
+            Objective::TotalSurplus => total_surplus(result, query),
+            Objective::FreeTraitSlots => result
+                .sigils
+                .iter()
+                .flat_map(|sigil| [Some(sigil.trait1), sigil.trait2])
+                .filter(|t| !t.is_some_and(|t| is_desired_trait(t, query)))
+                .count() as i64,
+            Objective::BonusTraits => {
+                let traits = result.traits();
+                traits
+                    .iter()
+                    .filter(|(kind, _)| !query.desired_traits.contains(**kind))
+                    .map(|(&kind, _)| traits.effective_level(kind) as i64)
+                    .sum()
+            }
+            Objective::LeastWaste => -(result.traits().wasted_levels() as i64),
+            Objective::OptionalTraits => query.optional_score(&result.traits()) as i64,
+            // the surplus is far below 2^32, so it only decides between equal sigil counts
+            Objective::FreeSlots => {
+                -((result.sigils.len() as i64) << 32) + total_surplus(result, query)
+            }
+        }
+    }
+}
+
+fn total_surplus(result: &SearchResult, query: &SearchQuery) -> i64 {
+    let traits = result.traits();
+    query
+        .desired_traits
+        .iter()
+        .map(|(&kind, _)| {
+            let desired = query.desired_traits.effective_level(kind);
+            traits.effective_level(kind).saturating_sub(desired) as i64
+        })
+        .sum()
+}
+
+/// keeps the `k` best results seen so far, preferring earlier results on ties
+pub(super) struct TopK {
+    k: usize,
+    seen: usize,
+    heap: BinaryHeap<Reverse<Ranked>>,
+}
+
+struct Ranked {
+    score: i64,
+    /// insertion order, so that earlier results rank higher on equal scores
+    order: Reverse<usize>,
+    result: SearchResult,
+}
+
+impl TopK {
+    pub(super) fn new(k: usize) -> Self {
+        TopK {
+            k,
+            seen: 0,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+
+    pub(super) fn push(&mut self, score: i64, result: SearchResult) {
+        if self.k == 0 {
+            return;
+        }
+
+        self.heap.push(Reverse(Ranked {
+            score,
+            order: Reverse(self.seen),
+            result,
+        }));
+        self.seen += 1;
+
+        if self.heap.len() > self.k {
+            self.heap.pop();
+        }
+    }
+
+    /// the score a result has to beat to be kept, once `k` results are kept
+    pub(super) fn worst_score(&self) -> Option<i64> {
+        if self.heap.len() < self.k {
+            return None;
+        }
+        self.heap.peek().map(|Reverse(ranked)| ranked.score)
+    }
+
+    /// returns the results ordered from best to worst
+    pub(super) fn into_sorted_vec(self) -> Vec<SearchResult> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(ranked)| ranked.result)
+            .collect()
+    }
+}
+
+impl Ranked {
+    fn key(&self) -> (i64, Reverse<usize>) {
+        (self.score, self.order)
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{OptionalTrait, SearchPool, Sigil, Trait, TraitKind as TK};
+    use crate::solver::testing::query;
+    use crate::solver::{BranchBoundSolver, GeneticSolver, NaiveSolver, Solver};
+
+    use super::*;
+
+    #[test]
+    fn scores() {
+        let query = SearchQuery {
+            optional_traits: vec![OptionalTrait {
+                kind: TK::HP,
+                level: 5,
+                weight: 3,
+            }],
+            ..query(&[(TK::DMGCap, 15)], 2)
+        };
+        let result = SearchResult::new(
+            vec![
+                Sigil {
+                    trait1: Trait::new(TK::DMGCap, 15),
+                    trait2: Some(Trait::new(TK::HP, 10)),
+                },
+                Sigil::new_single(Trait::new(TK::DMGCap, 5)),
+            ],
+            None,
+            None,
+        );
+
+        assert_eq!(Objective::TotalSurplus.score(&result, &query), 5);
+        assert_eq!(Objective::FreeTraitSlots.score(&result, &query), 2);
+        assert_eq!(Objective::BonusTraits.score(&result, &query), 10);
+        assert_eq!(Objective::LeastWaste.score(&result, &query), 0);
+        assert_eq!(Objective::OptionalTraits.score(&result, &query), 15);
+        assert_eq!(Objective::FreeSlots.score(&result, &query), -(2 << 32) + 5);
+    }
+
+    #[test]
+    fn fewest_sigils_are_proven_by_exact_solvers() {
+        let sigil = |level| Sigil::new_single(Trait::new(TK::DMGCap, level));
+        let pool = SearchPool::new(
+            vec![sigil(10), sigil(10), sigil(10), sigil(15), sigil(20)],
+            vec![],
+            vec![],
+        );
+        let query = query(&[(TK::DMGCap, 30)], 4);
+
+        for solver in [&NaiveSolver as &dyn Solver, &BranchBoundSolver] {
+            let smallest = solver.search_fewest_sigils(pool.clone(), &query, 2);
+
+            assert!(smallest.proven);
+            assert_eq!(
+                smallest.results,
+                [
+                    SearchResult::new(vec![sigil(15), sigil(20)], None, None),
+                    SearchResult::new(vec![sigil(10), sigil(20)], None, None),
+                ]
+            );
+        }
+
+        let genetic = GeneticSolver {
+            population_size: 10,
+            generations: 5,
+            tournament_size: 2,
+            crossover_rate: 0.5,
+            mutation_rate: 0.1,
+        };
+        assert!(!genetic.search_fewest_sigils(pool, &query, 1).proven);
+    }
+
+    #[test]
+    fn exact_solvers_maximise_optional_traits() {
+        let dmg_cap = Sigil::new_single(Trait::new(TK::DMGCap, 15));
+        let pool = SearchPool::new(
+            vec![
+                dmg_cap.clone(),
+                dmg_cap.clone(),
+                Sigil::new_single(Trait::new(TK::Aegis, 15)),
+                Sigil::new_single(Trait::new(TK::Regen, 15)),
+            ],
+            vec![],
+            vec![],
+        );
+        let optional = |kind, weight| OptionalTrait {
+            kind,
+            level: 15,
+            weight,
+        };
+        let query = SearchQuery {
+            optional_traits: vec![optional(TK::Aegis, 1), optional(TK::Regen, 2)],
+            ..query(&[(TK::DMGCap, 15)], 2)
+        };
+        let expected = SearchResult::new(
+            vec![dmg_cap, Sigil::new_single(Trait::new(TK::Regen, 15))],
+            None,
+            None,
+        );
+
+        for solver in [&NaiveSolver as &dyn Solver, &BranchBoundSolver] {
+            let best = solver.search_best(pool.clone(), &query, Objective::OptionalTraits, 1);
+            assert_eq!(
+                best,
+                [SearchResult::new(expected.sigils.clone(), None, None)]
+            );
+        }
+    }
+
+    #[test]
+    fn top_k_keeps_best_in_order() {
+        let mut top = TopK::new(2);
+        for level in [5, 15, 10, 15] {
+            top.push(
+                level as i64,
+                SearchResult::new(
+                    vec![Sigil::new_single(Trait::new(TK::DMGCap, level))],
+                    None,
+                    None,
+                ),
+            );
+        }
+
+        let levels: Vec<u8> = top
+            .into_sorted_vec()
+            .iter()
+            .map(|r| r.sigils[0].trait1.level)
+            .collect();
+        assert_eq!(levels, [15, 15]);
+    }
+}
@@ -0,0 +1,12 @@
+//! helpers shared by the solver tests
+
+use crate::model::{SearchQuery, Trait, TraitKind};
+
+/// a query for the given desired traits and number of sigil slots
+pub(super) fn query(traits: &[(TraitKind, u8)], sigil_slots: u8) -> SearchQuery {
+    SearchQuery {
+        desired_traits: traits.iter().map(|&(k, l)| Trait::new(k, l)).collect(),
+        sigil_slots,
+        ..Default::default()
+    }
+}
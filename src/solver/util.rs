@@ -1,47 +1,312 @@
-use std::collections::HashMap;
+use itertools::Itertools;
 
-use crate::model::{SearchPool, SearchQuery, SearchResult, Trait};
+use crate::model::{SearchPool, SearchQuery, SearchResult, Sigil, Stack, Trait, TraitSet, Weapon};
 
-/// filter out sigils and wrightstones that do not have any desired skills
+/// filter out sigils, wrightstones and weapons that do not have any desired or optional skills
+/// or that alone go above a max level of the query, keeping all weapons if none has one,
+/// and sigils with traits exclusive to a character other than the query's character.
+///
+/// the locked sigils are taken out of the pool, and a locked wrightstone replaces the pool's
+/// wrightstones, so that solvers only choose the items that are not locked
 pub fn filter_pool(pool: SearchPool, query: &SearchQuery) -> SearchPool {
     let SearchPool {
         mut sigils,
         mut wrightstones,
+        weapons,
     } = pool;
 
-    sigils = sigils
-        .into_iter()
-        .filter(|sigil| {
-            let trait1 = Some(sigil.trait1);
-            let trait2 = sigil.trait2;
-            trait1.is_some_and(|t| is_desired_trait(t, query))
-                || trait2.is_some_and(|t| is_desired_trait(t, query))
-        })
-        .collect();
+    for locked in &query.locked_sigils {
+        let stack = sigils
+            .iter_mut()
+            .find(|stack| stack.item == *locked && stack.count > 0);
+        if let Some(stack) = stack {
+            stack.count -= 1;
+        }
+    }
+    sigils.retain(|stack| stack.count > 0);
+
+    sigils.retain(|Stack { item: sigil, .. }| {
+        let trait1 = Some(sigil.trait1);
+        let trait2 = sigil.trait2;
+        (trait1.is_some_and(|t| is_wanted_trait(t, query))
+            || trait2.is_some_and(|t| is_wanted_trait(t, query)))
+            && trait1.is_some_and(|t| is_usable_trait(t, query))
+            && trait2.is_none_or(|t| is_usable_trait(t, query))
+            && is_within_max_levels(sigil.traits(), query)
+    });
 
-    wrightstones = wrightstones
+    wrightstones.retain(|Stack { item: stone, .. }| {
+        let trait1 = Some(stone.trait1);
+        let trait2 = stone.trait2;
+        let trait3 = stone.trait3;
+        (trait1.is_some_and(|t| is_wanted_trait(t, query))
+            || trait2.is_some_and(|t| is_wanted_trait(t, query))
+            || trait3.is_some_and(|t| is_wanted_trait(t, query)))
+            && is_within_max_levels(stone.traits(), query)
+    });
+    if let Some(locked) = &query.locked_wrightstone {
+        wrightstones = vec![Stack::new(locked.clone(), 1)];
+    }
+
+    // identical weapons would only repeat the same builds
+    let mut weapons: Vec<Weapon> = weapons
         .into_iter()
-        .filter(|stone| {
-            let trait1 = Some(stone.trait1);
-            let trait2 = stone.trait2;
-            let trait3 = stone.trait3;
-            trait1.is_some_and(|t| is_desired_trait(t, query))
-                || trait2.is_some_and(|t| is_desired_trait(t, query))
-                || trait3.is_some_and(|t| is_desired_trait(t, query))
-        })
+        .filter(|weapon| is_within_max_levels(weapon.traits(), query))
+        .unique()
         .collect();
+    // every build has a weapon, so if none has a wanted trait they are all kept to pick from
+    let has_wanted = |weapon: &Weapon| weapon.traits().any(|t| is_wanted_trait(t, query));
+    if weapons.iter().any(has_wanted) {
+        weapons.retain(has_wanted);
+    }
 
     SearchPool {
         sigils,
         wrightstones,
+        weapons,
     }
 }
 
+/// returns whether the locked sigils fit into the sigil slots and the pool holds a copy
+/// of every one of them, without which no build can be made
+pub fn can_lock(pool: &SearchPool, query: &SearchQuery) -> bool {
+    query.unlocked_slots().is_some() && missing_locked_sigils(pool, query) == 0
+}
+
+/// the number of locked sigils the pool has no copy left of
+pub fn missing_locked_sigils(pool: &SearchPool, query: &SearchQuery) -> usize {
+    let mut counts: Vec<u16> = pool.sigils.iter().map(|stack| stack.count).collect();
+    query
+        .locked_sigils
+        .iter()
+        .filter(|&locked| {
+            let stack =
+                (0..counts.len()).find(|&i| pool.sigils[i].item == *locked && counts[i] > 0);
+            match stack {
+                Some(i) => {
+                    counts[i] -= 1;
+                    false
+                }
+                None => true,
+            }
+        })
+        .count()
+}
+
 /// returns whether the given search result fulfills the search query
 pub fn is_valid_result(result: &SearchResult, query: &SearchQuery) -> bool {
-    result.traits().is_superset_of(&query.desired_traits)
+    let traits = result.traits();
+    traits.is_superset_of(&query.desired_traits) && !query.exceeds_max_levels(&traits)
 }
 
 pub fn is_desired_trait(t: Trait, query: &SearchQuery) -> bool {
     query.desired_traits.contains(t.kind)
 }
+
+/// returns whether the trait is desired or optional
+pub fn is_wanted_trait(t: Trait, query: &SearchQuery) -> bool {
+    query.wants(t.kind)
+}
+
+/// returns whether the trait has an effect for the query's character
+pub fn is_usable_trait(t: Trait, query: &SearchQuery) -> bool {
+    query
+        .character
+        .is_none_or(|character| t.kind.is_usable_by(character))
+}
+
+/// returns whether no sigil can be left out of the result, except for locked sigils,
+/// without missing a desired level or lowering the optional score
+pub fn is_minimal(result: &SearchResult, query: &SearchQuery) -> bool {
+    let traits = result.traits();
+    unlocked_sigils(result, query)
+        .into_iter()
+        .all(|sigil| !can_leave_out(&traits, sigil, query))
+}
+
+/// leaves out sigils one at a time, except for locked sigils, as long as the result
+/// keeps every desired level and its optional score
+pub fn minimize(mut result: SearchResult, query: &SearchQuery) -> SearchResult {
+    loop {
+        let traits = result.traits();
+        let Some(sigil) = unlocked_sigils(&result, query)
+            .into_iter()
+            .find(|sigil| can_leave_out(&traits, sigil, query))
+            .cloned()
+        else {
+            return result;
+        };
+
+        let i = result.sigils.iter().rposition(|s| *s == sigil).unwrap();
+        result.sigils.remove(i);
+    }
+}
+
+/// the sigils of the result that are not copies of a locked sigil
+fn unlocked_sigils<'r>(result: &'r SearchResult, query: &SearchQuery) -> Vec<&'r Sigil> {
+    let mut locked: Vec<&Sigil> = query.locked_sigils.iter().collect();
+    result
+        .sigils
+        .iter()
+        .filter(|sigil| match locked.iter().position(|l| l == sigil) {
+            Some(pos) => {
+                locked.swap_remove(pos);
+                false
+            }
+            None => true,
+        })
+        .collect()
+}
+
+fn can_leave_out(traits: &TraitSet, sigil: &Sigil, query: &SearchQuery) -> bool {
+    let mut without = traits.clone();
+    for t in sigil.traits() {
+        without.remove(t);
+    }
+    without.is_superset_of(&query.desired_traits)
+        && query.optional_score(&without) == query.optional_score(traits)
+}
+
+/// returns whether the traits of one item stay within the query's max levels
+pub fn is_within_max_levels(traits: impl Iterator<Item = Trait>, query: &SearchQuery) -> bool {
+    !query.exceeds_max_levels(&traits.collect())
+}
+
+/// every item as an option to pick from, or only `None` if there are no items,
+/// so that a build can still be made without any
+pub fn choices<T>(items: Vec<T>) -> Vec<Option<T>> {
+    if items.is_empty() {
+        vec![None]
+    } else {
+        items.into_iter().map(Some).collect()
+    }
+}
+
+/// every way to pick `k` items from stacks of identical items with the given `counts`,
+/// as the stack index of each picked item in ascending order.
+///
+/// picking different copies of an identical item is only generated once
+pub fn multiset_combinations(counts: Vec<u16>, k: usize) -> MultisetCombinations {
+    let mut suffix_total = vec![0; counts.len() + 1];
+    for i in (0..counts.len()).rev() {
+        suffix_total[i] = suffix_total[i + 1] + counts[i] as usize;
+    }
+
+    let next = fill(&counts, Vec::with_capacity(k), 0, k);
+    MultisetCombinations {
+        counts,
+        suffix_total,
+        k,
+        next,
+    }
+}
+
+pub struct MultisetCombinations {
+    counts: Vec<u16>,
+    /// the number of items in all stacks from index `i` onwards
+    suffix_total: Vec<usize>,
+    k: usize,
+    next: Option<Vec<usize>>,
+}
+
+impl Iterator for MultisetCombinations {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+
+        // find the last position that can move on to a later stack
+        // while still leaving enough items for the positions after it
+        for i in (0..current.len()).rev() {
+            let stack = current[i] + 1;
+            if self.suffix_total[stack.min(self.counts.len())] >= self.k - i {
+                let mut prefix = current[..i].to_vec();
+                prefix.reserve(self.k - i);
+                self.next = fill(&self.counts, prefix, stack, self.k);
+                break;
+            }
+        }
+
+        Some(current)
+    }
+}
+
+/// picks items from stacks `start` onwards until `picked` holds `k` items, taking as many
+/// as possible from each stack before moving on to the next
+fn fill(counts: &[u16], mut picked: Vec<usize>, start: usize, k: usize) -> Option<Vec<usize>> {
+    for (stack, &count) in counts.iter().enumerate().skip(start) {
+        let take = (count as usize).min(k - picked.len());
+        picked.extend(std::iter::repeat_n(stack, take));
+    }
+
+    (picked.len() == k).then_some(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::TraitKind as TK;
+    use crate::solver::testing::query;
+
+    use super::*;
+
+    #[test]
+    fn keeps_weapons_without_wanted_traits_if_no_weapon_has_one() {
+        let weapon = |name: &str, kind| Weapon {
+            name: name.to_string(),
+            trait1: Trait::new(kind, 10),
+            trait2: None,
+            trait3: None,
+        };
+        let query = query(&[(TK::Aegis, 15)], 1);
+        let plain = vec![weapon("Ascalon", TK::DMGCap), weapon("Ascalon", TK::DMGCap)];
+
+        let pool = filter_pool(SearchPool::new(vec![], vec![], plain.clone()), &query);
+        assert_eq!(pool.weapons, plain[..1]);
+
+        let mut weapons = plain;
+        weapons.push(weapon("Aegis Sword", TK::Aegis));
+        let pool = filter_pool(SearchPool::new(vec![], vec![], weapons), &query);
+        assert_eq!(pool.weapons.len(), 1);
+        assert_eq!(pool.weapons[0].name, "Aegis Sword");
+    }
+
+    #[test]
+    fn minimize_keeps_locked_sigils() {
+        let dmg_cap = Sigil::new_single(Trait::new(TK::DMGCap, 15));
+        let gamma = Sigil::new_single(Trait::new(TK::Gamma, 15));
+        let query = SearchQuery {
+            locked_sigils: vec![gamma.clone()],
+            allow_empty_slots: true,
+            ..query(&[(TK::DMGCap, 30)], 4)
+        };
+        let sigils = vec![
+            dmg_cap.clone(),
+            dmg_cap.clone(),
+            dmg_cap.clone(),
+            gamma.clone(),
+        ];
+        let result = SearchResult::new(sigils, None, None);
+
+        assert!(!is_minimal(&result, &query));
+        let minimal = minimize(result, &query);
+        assert!(is_minimal(&minimal, &query));
+        assert_eq!(minimal.sigils, [dmg_cap.clone(), dmg_cap, gamma]);
+    }
+
+    #[test]
+    fn multiset_combinations_are_unique() {
+        let combinations: Vec<Vec<usize>> = multiset_combinations(vec![2, 1, 2], 2).collect();
+
+        assert_eq!(
+            combinations,
+            [vec![0, 0], vec![0, 1], vec![0, 2], vec![1, 2], vec![2, 2]]
+        );
+    }
+
+    #[test]
+    fn multiset_combinations_edge_cases() {
+        assert_eq!(multiset_combinations(vec![1, 1], 0).count(), 1);
+        assert_eq!(multiset_combinations(vec![1, 1], 3).count(), 0);
+        assert_eq!(multiset_combinations(vec![3], 3).count(), 1);
+    }
+}